@@ -0,0 +1,214 @@
+use crate::errors::*;
+use std::fmt;
+use std::str::FromStr;
+
+use error_stack::{Report, Result};
+
+// Addresses are compressed public keys encoded with bech32 (BIP-173):
+// [human readable prefix of the network]1[key in 5 bit groups][6 checksum symbols]
+
+static CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+static GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+static CHECKSUM_SIZE: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Network {
+    Main,
+    Test,
+}
+
+impl Network {
+    /// human readable prefix of addresses
+    pub fn get_prefix(&self) -> &'static str {
+        match self {
+            Network::Main => "bct",
+            Network::Test => "tbct",
+        }
+    }
+
+    pub fn from_prefix(prefix: &str) -> Option<Network> {
+        match prefix {
+            "bct" => Some(Network::Main),
+            "tbct" => Some(Network::Test),
+            _ => None,
+        }
+    }
+
+    /// id of the network in the chain config
+    pub fn get_id(&self) -> u8 {
+        match self {
+            Network::Main => 0,
+            Network::Test => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Network> {
+        match id {
+            0 => Some(Network::Main),
+            1 => Some(Network::Test),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address {
+    network: Network,
+    key: [u8; 33],
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    let mut checksum: u32 = 1;
+    for value in values.iter() {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ (*value as u32);
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+fn expand_prefix(prefix: &str) -> Vec<u8> {
+    let mut expanded: Vec<u8> = Vec::with_capacity(prefix.len() * 2 + 1);
+    expanded.extend(prefix.bytes().map(|byte| byte >> 5));
+    expanded.push(0);
+    expanded.extend(prefix.bytes().map(|byte| byte & 31));
+    expanded
+}
+
+fn create_checksum(prefix: &str, data: &[u8]) -> [u8; 6] {
+    let mut values = expand_prefix(prefix);
+    values.extend(data);
+    values.extend([0u8; 6]);
+
+    let checksum = polymod(&values) ^ 1;
+
+    let mut result = [0u8; 6];
+    for (i, symbol) in result.iter_mut().enumerate() {
+        *symbol = ((checksum >> (5 * (5 - i))) & 31) as u8;
+    }
+    result
+}
+
+/// Regroups bits, returns None if padding isn't allowed and bits are left
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut accumulator: u32 = 0;
+    let mut bits: u32 = 0;
+    let max_value: u32 = (1 << to) - 1;
+    let mut result: Vec<u8> = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+
+    for value in data.iter() {
+        accumulator = (accumulator << from) | (*value as u32);
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            result.push(((accumulator >> bits) & max_value) as u8);
+        }
+    }
+
+    if pad {
+        if bits > 0 {
+            result.push(((accumulator << (to - bits)) & max_value) as u8);
+        }
+    } else if bits >= from || ((accumulator << (to - bits)) & max_value) != 0 {
+        return None;
+    }
+
+    Some(result)
+}
+
+impl Address {
+    pub fn new(key: [u8; 33], network: Network) -> Address {
+        Address { network, key }
+    }
+
+    pub fn get_key(&self) -> &[u8; 33] {
+        &self.key
+    }
+
+    pub fn get_network(&self) -> Network {
+        self.network
+    }
+
+    pub fn parse(encoded: &str) -> Result<Address, AddressError> {
+        if encoded.to_lowercase() != encoded && encoded.to_uppercase() != encoded {
+            return Err(Report::new(AddressError::Address(AddressErrorKind::Parse))
+                .attach_printable("mixed case"));
+        }
+        let encoded = encoded.to_lowercase();
+
+        let separator = match encoded.rfind('1') {
+            Some(separator) => separator,
+            None => {
+                return Err(Report::new(AddressError::Address(AddressErrorKind::Parse))
+                    .attach_printable("no separator"));
+            }
+        };
+
+        let prefix = &encoded[..separator];
+        let network = Network::from_prefix(prefix).ok_or_else(|| {
+            Report::new(AddressError::Address(AddressErrorKind::Network))
+                .attach_printable(format!("unknown prefix {}", prefix))
+        })?;
+
+        let mut data: Vec<u8> = Vec::with_capacity(encoded.len() - separator - 1);
+        for symbol in encoded[separator + 1..].bytes() {
+            let value = CHARSET
+                .iter()
+                .position(|character| *character == symbol)
+                .ok_or_else(|| {
+                    Report::new(AddressError::Address(AddressErrorKind::Parse))
+                        .attach_printable(format!("invalid character {}", symbol as char))
+                })?;
+            data.push(value as u8);
+        }
+
+        if data.len() < CHECKSUM_SIZE {
+            return Err(Report::new(AddressError::Address(AddressErrorKind::Length)));
+        }
+
+        let mut values = expand_prefix(prefix);
+        values.extend(&data);
+        if polymod(&values) != 1 {
+            return Err(Report::new(AddressError::Address(
+                AddressErrorKind::Checksum,
+            )));
+        }
+
+        let key = convert_bits(&data[..data.len() - CHECKSUM_SIZE], 5, 8, false)
+            .filter(|key| key.len() == 33)
+            .ok_or_else(|| Report::new(AddressError::Address(AddressErrorKind::Length)))?;
+
+        let mut address = Address::new([0u8; 33], network);
+        address.key.copy_from_slice(&key);
+
+        Ok(address)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = self.network.get_prefix();
+        let mut data = convert_bits(&self.key, 8, 5, true).unwrap();
+        let checksum = create_checksum(prefix, &data);
+        data.extend(checksum);
+
+        let encoded: String = data
+            .iter()
+            .map(|value| CHARSET[*value as usize] as char)
+            .collect();
+
+        write!(f, "{}1{}", prefix, encoded)
+    }
+}
+
+impl FromStr for Address {
+    type Err = Report<AddressError>;
+
+    fn from_str(s: &str) -> Result<Self, AddressError> {
+        Address::parse(s)
+    }
+}
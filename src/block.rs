@@ -1,10 +1,12 @@
 use crate::dump_headers::Headers;
 use crate::errors::*;
 use crate::merkletree::MerkleTree;
+use crate::registry;
 use crate::tools;
 use crate::transaction::{Transaction, Transactionable};
 use byteorder::{BigEndian, ReadBytesExt};
 use num_bigint::BigUint;
+use num_traits::Zero;
 use std::convert::TryInto;
 use std::mem::transmute;
 //use std::mem::transmute_copy;
@@ -12,7 +14,6 @@ use std::mem::transmute;
 use error_stack::{Report, Result, ResultExt};
 
 #[macro_export]
-
 macro_rules! bytes_to_u64 {
     ($buffer:expr,$buffer_index:expr) => {
         (&$buffer[$buffer_index..$buffer_index + 8])
@@ -31,6 +32,7 @@ pub struct BasicInfo {
     current_hash: [u8; 32],
     height: u64,
     difficulty: [u8; 32],
+    state_root: [u8; 32],
 }
 
 impl BasicInfo {
@@ -42,6 +44,7 @@ impl BasicInfo {
         current_hash: [u8; 32],
         height: u64,
         difficulty: [u8; 32],
+        state_root: [u8; 32],
     ) -> BasicInfo {
         BasicInfo {
             //miner:miner,
@@ -51,12 +54,31 @@ impl BasicInfo {
             current_hash,
             height,
             difficulty,
+            state_root,
         }
     }
 
     pub fn get_dump_size(&self) -> usize {
-        8 + tools::bigint_size(&self.pow) + 32 + 32 + 8 + 32
+        8 + tools::bigint_size(&self.pow) + 32 + 32 + 8 + 32 + 32
+    }
+
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn get_previous_hash(&self) -> &[u8; 32] {
+        &self.previous_hash
+    }
+
+    pub fn get_height(&self) -> u64 {
+        self.height
+    }
+
+    /// root of the summary state after applying the block
+    pub fn get_state_root(&self) -> &[u8; 32] {
+        &self.state_root
     }
+
     pub fn dump(&self, buffer: &mut Vec<u8>) -> Result<(), BlockError> {
         // dumping timestamp
         for byte in self.timestamp.to_be_bytes().iter() {
@@ -81,6 +103,9 @@ impl BasicInfo {
         // dumping difficulty
         buffer.extend(self.difficulty);
 
+        // dumping state root
+        buffer.extend(self.state_root);
+
         // dumping PoW
         tools::dump_biguint(&self.pow, buffer)
             .change_context(BlockError::BasicInfo(BasicInfoErrorKind::Dump))?;
@@ -91,10 +116,10 @@ impl BasicInfo {
     pub fn parse(data: &[u8]) -> Result<BasicInfo, BlockError> {
         let mut index: usize = 0;
 
-        if data.len() <= 112 {
+        if data.len() <= 144 {
             return Err(
                 Report::new(BlockError::BasicInfo(BasicInfoErrorKind::Parse))
-                    .attach_printable("data <= 112"),
+                    .attach_printable("data <= 144"),
             );
         }
 
@@ -120,6 +145,10 @@ impl BasicInfo {
         let difficulty: [u8; 32] = unsafe { data[index..index + 32].try_into().unwrap_unchecked() };
         index += 32;
 
+        // parsing state root
+        let state_root: [u8; 32] = unsafe { data[index..index + 32].try_into().unwrap_unchecked() };
+        index += 32;
+
         // parsing PoW
         let (pow, _) = tools::load_biguint(&data[index..])
             .change_context(BlockError::BasicInfo(BasicInfoErrorKind::Parse))
@@ -132,6 +161,7 @@ impl BasicInfo {
             current_hash,
             height,
             difficulty,
+            state_root,
         })
     }
 }
@@ -161,20 +191,53 @@ impl TransactionBlock {
         }
     }
 
+    pub fn get_transactions(&self) -> &[Box<dyn Transactionable>] {
+        &self.transactions
+    }
+
+    pub fn into_transactions(self) -> Vec<Box<dyn Transactionable>> {
+        self.transactions
+    }
+
+    pub fn get_merkle_tree_root(&self) -> &[u8; 32] {
+        &self.merkle_tree_root
+    }
+
+    pub fn get_fee(&self) -> &BigUint {
+        &self.fee
+    }
+
+    pub fn get_default_info(&self) -> &BasicInfo {
+        &self.default_info
+    }
+
+    /// Sum of fees paid by the transactions
+    pub fn calculate_fee(transactions: &[Box<dyn Transactionable>]) -> BigUint {
+        let mut fee = BigUint::zero();
+        for transaction in transactions.iter() {
+            if let Some(tx_fee) = transaction.get_fee() {
+                fee += tx_fee;
+            }
+        }
+        fee
+    }
+
+    /// Checks that block fee equals the sum of transactions fees
+    pub fn check_fee(&self) -> bool {
+        TransactionBlock::calculate_fee(&self.transactions) == self.fee
+    }
+
     pub fn merkle_tree_is_built(&self) -> bool {
         self.merkle_tree.is_some()
     }
 
     pub fn build_merkle_tree(&mut self) -> Result<(), BlockError> {
         let mut new_merkle_tree = MerkleTree::new();
-        let mut hashes: Vec<&[u8; 32]> = Vec::with_capacity(self.transactions.len());
 
-        for tx in self.transactions.iter() {
-            let res = tx.hash(&self.default_info.previous_hash);
-            hashes.push(Box::leak(Box::new(res)));
-        }
+        // leaves are stable ids, so they don't depend on the previous block
+        let ids: Vec<[u8; 32]> = self.transactions.iter().map(|tx| tx.get_id()).collect();
 
-        let res = new_merkle_tree.add_objects(hashes);
+        let res = new_merkle_tree.add_objects(ids.iter().collect());
         if !res {
             return Err(Report::new(BlockError::TransactionBlock(
                 TxBlockErrorKind::BuildingMerkleTree,
@@ -184,6 +247,32 @@ impl TransactionBlock {
         Ok(())
     }
 
+    /// Merkle proof of inclusion of the transaction at the given position
+    pub fn get_proof(&mut self, position: usize) -> Result<Vec<[u8; 32]>, BlockError> {
+        let hash = match self.transactions.get(position) {
+            Some(transaction) => transaction.get_id(),
+            None => {
+                return Err(Report::new(BlockError::TransactionBlock(
+                    TxBlockErrorKind::GettingProof,
+                ))
+                .attach_printable(format!("no transaction at position {}", position)));
+            }
+        };
+
+        if !self.merkle_tree_is_built() {
+            self.build_merkle_tree()?;
+        }
+
+        let proof = self
+            .merkle_tree
+            .as_ref()
+            .unwrap()
+            .get_proof(&hash)
+            .change_context(BlockError::TransactionBlock(TxBlockErrorKind::GettingProof))?;
+
+        Ok(proof.into_iter().copied().collect())
+    }
+
     pub fn check_merkle_tree(&mut self) -> Result<bool, BlockError> {
         // build merkle tree if not built
         if !self.merkle_tree_is_built() {
@@ -297,32 +386,19 @@ impl TransactionBlock {
 
             offset += 4; // inc offset
 
-            let header = Headers::from_u8(data[offset])
-                .change_context(BlockError::TransactionBlock(TxBlockErrorKind::Parse))?;
+            let header = data[offset];
             offset += 1;
 
-            //let mut trtk: TransactionToken = TransactionToken::new(None, None);
-            let tr = match header {
-                Headers::Transaction => Transaction::parse(
-                    &data[offset..offset + (transaction_size as usize)],
-                    transaction_size as u64,
-                )
-                .change_context(BlockError::TransactionBlock(TxBlockErrorKind::Parse))?,
-                Headers::Token => {
-                    return Err(Report::new(BlockError::NotImplemented(
-                        NotImplementedKind::Token,
-                    )));
-                }
-                _ => {
-                    return Err(Report::new(BlockError::HeaderError(
-                        DumpHeadersErrorKind::WrongHeader,
-                    )));
-                }
-            };
+            let tr = registry::parse_body(
+                header,
+                &data[offset..offset + (transaction_size as usize)],
+                transaction_size as u64,
+            )
+            .change_context(BlockError::TransactionBlock(TxBlockErrorKind::Parse))?;
 
             offset += transaction_size as usize; // inc offset
 
-            transactions.push(Box::new(tr));
+            transactions.push(tr);
         }
 
         if offset != block_size as usize {
@@ -582,10 +658,19 @@ impl SumTransactionBlock {
     }
 
     pub fn is_transaction_block(&self) -> bool {
-        self.transaction_block.is_none()
+        self.transaction_block.is_some()
     }
     pub fn is_summarize_block(&self) -> bool {
-        self.summarize_block.is_none()
+        self.summarize_block.is_some()
+    }
+    pub fn get_transaction_block(&self) -> Option<&TransactionBlock> {
+        self.transaction_block.as_ref()
+    }
+    pub fn get_summarize_block(&self) -> Option<&SummarizeBlock> {
+        self.summarize_block.as_ref()
+    }
+    pub fn into_transaction_block(self) -> Option<TransactionBlock> {
+        self.transaction_block
     }
     pub fn hash(&self) -> Result<[u8; 32], BlockError> {
         if self.is_transaction_block() {
@@ -597,17 +682,17 @@ impl SumTransactionBlock {
 
     pub fn get_dump_size(&self) -> usize {
         if self.is_transaction_block() {
-            return self.transaction_block.as_ref().unwrap().get_dump_size();
+            self.transaction_block.as_ref().unwrap().get_dump_size()
         } else {
-            return self.summarize_block.as_ref().unwrap().get_dump_size();
+            self.summarize_block.as_ref().unwrap().get_dump_size()
         }
     }
 
     pub fn dump(&self) -> Result<Vec<u8>, BlockError> {
         if self.is_transaction_block() {
-            return self.transaction_block.as_ref().unwrap().dump();
+            self.transaction_block.as_ref().unwrap().dump()
         } else {
-            return self.summarize_block.as_ref().unwrap().dump();
+            self.summarize_block.as_ref().unwrap().dump()
         }
     }
 }
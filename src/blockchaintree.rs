@@ -1,9 +1,22 @@
 #![allow(non_snake_case)]
+use crate::address::{Address, Network};
 use crate::block::{SumTransactionBlock, SummarizeBlock, TokenBlock, TransactionBlock};
+use crate::fee_estimation::{block_min_fee_rate, estimate_fee_rate, FEE_HISTORY_BLOCKS};
+use crate::history::{HistoryCursor, HistoryEntry, HistoryPage};
+use crate::memo::check_payload_fee;
+use crate::mempool::{spent_amount, Mempool};
+use crate::multisig::MultisigAccount;
+use crate::pool_journal::PoolJournal;
+use crate::receipt::Receipt;
+use crate::state::{is_balance_storable, Account, StateTree};
+use crate::token_ledger::{TokenLedger, TokenState};
 use crate::tools;
-use crate::transaction::{Transaction, Transactionable};
+use crate::transaction::{
+    is_expired, is_nonce_required, is_unlocked, Transaction, Transactionable,
+};
+use crate::verifier::BatchVerifier;
 use num_bigint::BigUint;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 
 use crate::dump_headers::Headers;
@@ -17,27 +30,35 @@ use std::io::Read;
 use std::io::Write;
 use std::path::Path;
 use std::str;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::errors::*;
 use error_stack::{IntoReport, Report, Result, ResultExt};
 
-static BLOCKCHAIN_DIRECTORY: &str = "./BlockChainTree/";
+/// default root of the tree, folders below are relative to the root
+pub static BLOCKCHAIN_DIRECTORY: &str = "./BlockChainTree/";
 
-static AMMOUNT_SUMMARY: &str = "./BlockChainTree/SUMMARY/";
-static OLD_AMMOUNT_SUMMARY: &str = "./BlockChainTree/SUMMARYOLD/";
+static AMMOUNT_SUMMARY: &str = "SUMMARY/";
+static OLD_AMMOUNT_SUMMARY: &str = "SUMMARYOLD/";
+static TOKENS_SUMMARY: &str = "TOKENS/";
 
-static MAIN_CHAIN_DIRECTORY: &str = "./BlockChainTree/MAIN/";
+static MAIN_CHAIN_DIRECTORY: &str = "MAIN/";
 
-static DERIVATIVE_CHAINS_DIRECTORY: &str = "./BlockChainTree/DERIVATIVES/";
+static DERIVATIVE_CHAINS_DIRECTORY: &str = "DERIVATIVES/";
 static CHAINS_FOLDER: &str = "CHAINS/";
 //static DERIVATIVE_DB_DIRECTORY: BlockChainTreeError = "./BlockChainTree/DERIVATIVE/DB/";
 
 static BLOCKS_FOLDER: &str = "BLOCKS/";
 static REFERENCES_FOLDER: &str = "REF/";
+static PAYLOADS_FOLDER: &str = "PAYLOADS/";
+static TRANSACTIONS_FOLDER: &str = "TRANSACTIONS/";
+static HISTORY_FOLDER: &str = "HISTORY/";
 
 static CONFIG_FILE: &str = "Chain.config";
 static LOOKUP_TABLE_FILE: &str = "LookUpTable.dat";
 static TRANSACTIONS_POOL: &str = "TRXS_POOL.pool";
+static NONCE_PREFIX: u8 = b'N';
+static MULTISIG_PREFIX: u8 = b'M';
 static GENESIS_BLOCK: [u8; 32] = [
     0x77, 0xe6, 0xd9, 0x52, 0x67, 0x57, 0x8e, 0x85, 0x39, 0xa9, 0xcf, 0xe0, 0x03, 0xf4, 0xf7, 0xfe,
     0x7d, 0x6a, 0x29, 0x0d, 0xaf, 0xa7, 0x73, 0xa6, 0x5c, 0x0f, 0x01, 0x9d, 0x5c, 0xbc, 0x0a, 0x7c,
@@ -49,25 +70,96 @@ static BEGINNING_DIFFICULTY: [u8; 32] = [
 // God is dead, noone will stop anarchy
 
 static MAX_TRANSACTIONS_PER_BLOCK: usize = 3000;
-static BLOCKS_PER_ITERATION: usize = 12960;
+/// how far into the future block timestamps are allowed to be, in seconds
+static MAX_FUTURE_BLOCK_TIME: u64 = 2 * 60 * 60;
+
+/// key of the address' nonce in the summary db
+fn nonce_key(addr: &[u8; 33]) -> [u8; 34] {
+    let mut key = [NONCE_PREFIX; 34];
+    key[1..].copy_from_slice(addr);
+    key
+}
+
+/// key of the key set registered for the multisig address in the summary db
+fn multisig_key(addr: &[u8; 33]) -> [u8; 34] {
+    let mut key = [MULTISIG_PREFIX; 34];
+    key[1..].copy_from_slice(addr);
+    key
+}
+
+/// height of the block and position of the transaction in it
+fn dump_location(height: u64, position: usize) -> [u8; 12] {
+    let mut location = [0u8; 12];
+    location[..8].copy_from_slice(&height.to_be_bytes());
+    location[8..].copy_from_slice(&(position as u32).to_be_bytes());
+    location
+}
+
+fn parse_location(data: &[u8]) -> Option<(u64, usize)> {
+    if data.len() != 12 {
+        return None;
+    }
+    let height = u64::from_be_bytes(data[..8].try_into().unwrap());
+    let position = u32::from_be_bytes(data[8..].try_into().unwrap());
+    Some((height, position as usize))
+}
+
+/// key of the transaction in the history of the address
+fn history_key(addr: &[u8; 33], height: u64, position: usize) -> [u8; 45] {
+    let mut key = [0u8; 45];
+    key[..33].copy_from_slice(addr);
+    key[33..].copy_from_slice(&dump_location(height, position));
+    key
+}
+
+/// folder of the derivative chain of the address
+fn derivative_chain_path(root: &str, addr: &[u8; 33], network: Network) -> String {
+    String::from(root)
+        + DERIVATIVE_CHAINS_DIRECTORY
+        + &Address::new(*addr, network).to_string()
+        + "/"
+}
+
+fn legacy_derivative_chain_path(root: &str, addr: &[u8; 33]) -> String {
+    String::from(root) + DERIVATIVE_CHAINS_DIRECTORY + &addr.encode_hex::<String>() + "/"
+}
+
+/// seconds since unix epoch
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
 
 pub struct Chain {
+    root: String,
     db: Db,
     height_reference: Db,
+    payload_reference: Db,
+    transaction_reference: Db,
+    history_reference: Db,
     height: u64,
     genesis_hash: [u8; 32],
     difficulty: [u8; 32],
+    network: Network,
 }
 
 impl Chain {
-    pub fn new() -> Result<Chain, BlockChainTreeError> {
-        let root = String::from(MAIN_CHAIN_DIRECTORY);
+    pub fn new(root_path: &str) -> Result<Chain, BlockChainTreeError> {
+        let root = String::from(root_path);
         let path_blocks_st = root.clone() + BLOCKS_FOLDER;
         let path_references_st = root.clone() + REFERENCES_FOLDER;
+        let path_payloads_st = root.clone() + PAYLOADS_FOLDER;
+        let path_transactions_st = root.clone() + TRANSACTIONS_FOLDER;
+        let path_history_st = root.clone() + HISTORY_FOLDER;
         let path_height_st = root + CONFIG_FILE;
 
         let path_blocks = Path::new(&path_blocks_st);
         let path_reference = Path::new(&path_references_st);
+        let path_payloads = Path::new(&path_payloads_st);
+        let path_transactions = Path::new(&path_transactions_st);
+        let path_history = Path::new(&path_history_st);
         let path_height = Path::new(&path_height_st);
 
         // open blocks DB
@@ -82,6 +174,24 @@ impl Chain {
             .change_context(BlockChainTreeError::Chain(ChainErrorKind::Init))
             .attach_printable("failed to open references db")?;
 
+        // open payload references DB
+        let payload_reference = sled::open(path_payloads)
+            .report()
+            .change_context(BlockChainTreeError::Chain(ChainErrorKind::Init))
+            .attach_printable("failed to open payload references db")?;
+
+        // open transaction references DB
+        let transaction_reference = sled::open(path_transactions)
+            .report()
+            .change_context(BlockChainTreeError::Chain(ChainErrorKind::Init))
+            .attach_printable("failed to open transaction references db")?;
+
+        // open address history DB
+        let history_reference = sled::open(path_history)
+            .report()
+            .change_context(BlockChainTreeError::Chain(ChainErrorKind::Init))
+            .attach_printable("failed to open address history db")?;
+
         let mut file = File::open(path_height)
             .report()
             .change_context(BlockChainTreeError::Chain(ChainErrorKind::Init))?;
@@ -110,12 +220,35 @@ impl Chain {
             .change_context(BlockChainTreeError::Chain(ChainErrorKind::Init))
             .attach_printable("failed to read difficulty")?;
 
+        // read network, configs written before it was added belong to the main network
+        let mut network_id: [u8; 1] = [0];
+        let network_id = match file.read_exact(&mut network_id) {
+            Ok(()) => network_id[0],
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => {
+                Network::Main.get_id()
+            }
+            Err(error) => {
+                return Err(Report::new(error)
+                    .change_context(BlockChainTreeError::Chain(ChainErrorKind::Init))
+                    .attach_printable("failed to read network"));
+            }
+        };
+        let network = Network::from_id(network_id).ok_or_else(|| {
+            Report::new(BlockChainTreeError::Chain(ChainErrorKind::Init))
+                .attach_printable(format!("unknown network {}", network_id))
+        })?;
+
         Ok(Chain {
+            root: String::from(root_path),
             db,
             height_reference,
+            payload_reference,
+            transaction_reference,
+            history_reference,
             height,
             genesis_hash,
             difficulty,
+            network,
         })
     }
 
@@ -139,6 +272,50 @@ impl Chain {
             .report()
             .change_context(BlockChainTreeError::Chain(ChainErrorKind::AddingBlock))?;
 
+        // index transactions and payloads anchored by the block
+        if let Some(transaction_block) = block.get_transaction_block() {
+            let mut payloads = sled::Batch::default();
+            let mut transactions = sled::Batch::default();
+            let mut history = sled::Batch::default();
+            let mut anchored: HashSet<[u8; 32]> = HashSet::new();
+            for (position, transaction) in transaction_block.get_transactions().iter().enumerate() {
+                // payload stays anchored by the earliest block containing it
+                if let Some(payload) = transaction.get_payload() {
+                    let payload_hash = tools::hash(payload);
+                    let is_anchored = self
+                        .payload_reference
+                        .contains_key(payload_hash)
+                        .report()
+                        .change_context(BlockChainTreeError::Chain(ChainErrorKind::AddingBlock))?;
+
+                    if !is_anchored && anchored.insert(payload_hash) {
+                        payloads.insert(&payload_hash, &self.height.to_be_bytes());
+                    }
+                }
+
+                transactions.insert(&transaction.get_id(), &dump_location(self.height, position));
+
+                for addr in [transaction.get_sender(), transaction.get_receiver()] {
+                    history.insert(&history_key(addr, self.height, position) as &[u8], &[]);
+                }
+            }
+
+            self.payload_reference
+                .apply_batch(payloads)
+                .report()
+                .change_context(BlockChainTreeError::Chain(ChainErrorKind::AddingBlock))?;
+
+            self.transaction_reference
+                .apply_batch(transactions)
+                .report()
+                .change_context(BlockChainTreeError::Chain(ChainErrorKind::AddingBlock))?;
+
+            self.history_reference
+                .apply_batch(history)
+                .report()
+                .change_context(BlockChainTreeError::Chain(ChainErrorKind::AddingBlock))?;
+        }
+
         self.height += 1;
 
         self.db
@@ -153,6 +330,24 @@ impl Chain {
             .report()
             .change_context(BlockChainTreeError::Chain(ChainErrorKind::AddingBlock))?;
 
+        self.payload_reference
+            .flush_async()
+            .await
+            .report()
+            .change_context(BlockChainTreeError::Chain(ChainErrorKind::AddingBlock))?;
+
+        self.transaction_reference
+            .flush_async()
+            .await
+            .report()
+            .change_context(BlockChainTreeError::Chain(ChainErrorKind::AddingBlock))?;
+
+        self.history_reference
+            .flush_async()
+            .await
+            .report()
+            .change_context(BlockChainTreeError::Chain(ChainErrorKind::AddingBlock))?;
+
         Ok(())
     }
 
@@ -164,6 +359,11 @@ impl Chain {
         self.difficulty
     }
 
+    /// Network addresses of the chain are encoded for
+    pub fn get_network(&self) -> Network {
+        self.network
+    }
+
     pub fn find_by_height(
         &self,
         height: u64,
@@ -229,9 +429,85 @@ impl Chain {
         Ok(block)
     }
 
+    /// Height of the block containing payload with the given hash
+    pub fn find_by_payload_hash(
+        &self,
+        hash: &[u8; 32],
+    ) -> Result<Option<u64>, BlockChainTreeError> {
+        let height = self
+            .payload_reference
+            .get(hash)
+            .report()
+            .change_context(BlockChainTreeError::Chain(ChainErrorKind::FindByHashE))?;
+
+        match height {
+            None => Ok(None),
+            Some(height) => {
+                let height: [u8; 8] = height
+                    .as_ref()
+                    .try_into()
+                    .report()
+                    .change_context(BlockChainTreeError::Chain(ChainErrorKind::FindByHashE))?;
+                Ok(Some(u64::from_be_bytes(height)))
+            }
+        }
+    }
+
+    /// Height of the block containing transaction with the given key
+    /// and position of the transaction in it
+    pub fn find_transaction(
+        &self,
+        key: &[u8; 32],
+    ) -> Result<Option<(u64, usize)>, BlockChainTreeError> {
+        let location = self
+            .transaction_reference
+            .get(key)
+            .report()
+            .change_context(BlockChainTreeError::Chain(ChainErrorKind::FindTransaction))?;
+
+        match location {
+            None => Ok(None),
+            Some(location) => parse_location(&location).map(Some).ok_or_else(|| {
+                Report::new(BlockChainTreeError::Chain(ChainErrorKind::FindTransaction))
+                    .attach_printable("transaction location is corrupted")
+            }),
+        }
+    }
+
+    /// Locations of up to `limit` transactions of the address, from the newest,
+    /// starting before the given location
+    pub fn find_address_history(
+        &self,
+        addr: &[u8; 33],
+        before: Option<(u64, usize)>,
+        limit: usize,
+    ) -> Result<Vec<(u64, usize)>, BlockChainTreeError> {
+        let start = history_key(addr, 0, 0);
+        let entries = match before {
+            Some((height, position)) => self
+                .history_reference
+                .range(start..history_key(addr, height, position)),
+            None => self.history_reference.scan_prefix(addr),
+        };
+
+        let mut locations: Vec<(u64, usize)> = Vec::with_capacity(limit);
+        for entry in entries.rev().take(limit) {
+            let (key, _) = entry
+                .report()
+                .change_context(BlockChainTreeError::Chain(ChainErrorKind::FindHistory))?;
+
+            let location = parse_location(&key[33..]).ok_or_else(|| {
+                Report::new(BlockChainTreeError::Chain(ChainErrorKind::FindHistory))
+                    .attach_printable("history entry is corrupted")
+            })?;
+            locations.push(location);
+        }
+
+        Ok(locations)
+    }
+
     pub fn dump_config(&self) -> Result<(), BlockChainTreeError> {
-        let root = String::from(MAIN_CHAIN_DIRECTORY);
-        let path_config = root + CONFIG_FILE;
+        let path_config = self.root.clone() + CONFIG_FILE;
 
         let mut file = File::create(path_config)
             .report()
@@ -252,19 +528,31 @@ impl Chain {
             .change_context(BlockChainTreeError::Chain(ChainErrorKind::DumpConfig))
             .attach_printable("failes to write difficulty")?;
 
+        file.write_all(&[self.network.get_id()])
+            .report()
+            .change_context(BlockChainTreeError::Chain(ChainErrorKind::DumpConfig))
+            .attach_printable("failed to write network")?;
+
         Ok(())
     }
 
     pub fn new_without_config(
         root_path: &str,
         genesis_hash: &[u8; 32],
+        network: Network,
     ) -> Result<Chain, BlockChainTreeError> {
         let root = String::from(root_path);
         let path_blocks_st = root.clone() + BLOCKS_FOLDER;
-        let path_references_st = root + REFERENCES_FOLDER;
+        let path_references_st = root.clone() + REFERENCES_FOLDER;
+        let path_payloads_st = root.clone() + PAYLOADS_FOLDER;
+        let path_transactions_st = root.clone() + TRANSACTIONS_FOLDER;
+        let path_history_st = root + HISTORY_FOLDER;
 
         let path_blocks = Path::new(&path_blocks_st);
         let path_reference = Path::new(&path_references_st);
+        let path_payloads = Path::new(&path_payloads_st);
+        let path_transactions = Path::new(&path_transactions_st);
+        let path_history = Path::new(&path_history_st);
 
         // open blocks DB
         let db = sled::open(path_blocks)
@@ -282,15 +570,63 @@ impl Chain {
             ))
             .attach_printable("failed to open references db")?;
 
+        // open payload references DB
+        let payload_reference = sled::open(path_payloads)
+            .report()
+            .change_context(BlockChainTreeError::Chain(
+                ChainErrorKind::InitWithoutConfig,
+            ))
+            .attach_printable("failed to open payload references db")?;
+
+        // open transaction references DB
+        let transaction_reference = sled::open(path_transactions)
+            .report()
+            .change_context(BlockChainTreeError::Chain(
+                ChainErrorKind::InitWithoutConfig,
+            ))
+            .attach_printable("failed to open transaction references db")?;
+
+        // open address history DB
+        let history_reference = sled::open(path_history)
+            .report()
+            .change_context(BlockChainTreeError::Chain(
+                ChainErrorKind::InitWithoutConfig,
+            ))
+            .attach_printable("failed to open address history db")?;
+
         Ok(Chain {
+            root: String::from(root_path),
             db,
             height_reference,
+            payload_reference,
+            transaction_reference,
+            history_reference,
             height: 0,
             genesis_hash: *genesis_hash,
             difficulty: BEGINNING_DIFFICULTY,
+            network,
         })
     }
 
+    /// Hash of the last block, genesis hash for the empty chain
+    pub fn get_last_hash(&self) -> Result<[u8; 32], BlockChainTreeError> {
+        if self.height == 0 {
+            return Ok(self.genesis_hash);
+        }
+
+        let dump = self
+            .db
+            .get((self.height - 1).to_be_bytes())
+            .report()
+            .change_context(BlockChainTreeError::Chain(ChainErrorKind::FindByHeight))?
+            .ok_or_else(|| {
+                Report::new(BlockChainTreeError::Chain(ChainErrorKind::FindByHeight))
+                    .attach_printable("last block is missing")
+            })?;
+
+        Ok(tools::hash(&dump))
+    }
+
     pub fn get_last_block(&self) -> Result<Option<SumTransactionBlock>, BlockChainTreeError> {
         self.find_by_height(self.height - 1)
     }
@@ -584,15 +920,25 @@ impl DerivativeChain {
 }
 
 pub struct BlockChainTree {
-    trxs_pool: VecDeque<Box<dyn Transactionable>>,
+    root: String,
+    trxs_pool: Mempool,
+    pool_journal: PoolJournal,
+    verifier: BatchVerifier,
     summary_db: Option<Db>,
     old_summary_db: Option<Db>,
+    token_ledger: TokenLedger,
     main_chain: Chain,
 }
 
 impl BlockChainTree {
     pub fn with_config() -> Result<BlockChainTree, BlockChainTreeError> {
-        let summary_db_path = Path::new(&AMMOUNT_SUMMARY);
+        BlockChainTree::with_config_at(BLOCKCHAIN_DIRECTORY)
+    }
+
+    /// Opens the tree stored in the root folder
+    pub fn with_config_at(root: &str) -> Result<BlockChainTree, BlockChainTreeError> {
+        let summary_db_path = String::from(root) + AMMOUNT_SUMMARY;
+        let summary_db_path = Path::new(&summary_db_path);
 
         // open summary db
         let summary_db = sled::open(summary_db_path)
@@ -600,7 +946,8 @@ impl BlockChainTree {
             .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))
             .attach_printable("failed to open summary db")?;
 
-        let old_summary_db_path = Path::new(&OLD_AMMOUNT_SUMMARY);
+        let old_summary_db_path = String::from(root) + OLD_AMMOUNT_SUMMARY;
+        let old_summary_db_path = Path::new(&old_summary_db_path);
 
         // open old summary db
         let old_summary_db = sled::open(old_summary_db_path)
@@ -608,76 +955,78 @@ impl BlockChainTree {
             .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))
             .attach_printable("failed to open old summary db")?;
 
-        // read transactions pool
-        let pool_path = String::from(BLOCKCHAIN_DIRECTORY) + TRANSACTIONS_POOL;
-        let pool_path = Path::new(&pool_path);
-
-        let mut file = File::open(pool_path)
-            .report()
-            .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))
-            .attach_printable("failed to open transactions pool")?;
-
-        // read amount of transactions
-        let mut buf: [u8; 8] = [0; 8];
-        file.read_exact(&mut buf)
-            .report()
-            .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))
-            .attach_printable("failed to read amount of transactions")?;
-
-        let trxs_amount = u64::from_be_bytes(buf);
-
-        let mut buf: [u8; 4] = [0; 4];
+        // open token ledger
+        let token_ledger = TokenLedger::open(Path::new(&(String::from(root) + TOKENS_SUMMARY)))
+            .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))?;
 
-        // allocate VecDeque
-        let mut trxs_pool =
-            VecDeque::<Box<dyn Transactionable>>::with_capacity(trxs_amount as usize);
+        // opening main chain
+        let main_chain = Chain::new(&(String::from(root) + MAIN_CHAIN_DIRECTORY))
+            .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))?;
 
-        // parsing transactions
-        for _ in 0..trxs_amount {
-            file.read_exact(&mut buf)
-                .report()
-                .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))
-                .attach_printable("failed to read transaction size")?;
+        // replay transactions pool journal
+        let pool_path = String::from(root) + TRANSACTIONS_POOL;
 
-            let tr_size = u32::from_be_bytes(buf);
+        let mut pool_journal = PoolJournal::open(Path::new(&pool_path))
+            .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))?;
 
-            let mut transaction_buffer = vec![0u8; (tr_size - 1) as usize];
+        let transactions = pool_journal
+            .replay()
+            .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))?;
 
-            file.read_exact(&mut transaction_buffer)
-                .report()
-                .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))
-                .attach_printable("failed to read transaction")?;
-
-            if transaction_buffer[0] == 0 {
-                let transaction =
-                    Transaction::parse(&transaction_buffer[1..], (tr_size - 1) as u64)
-                        .change_context(BlockChainTreeError::BlockChainTree(
-                            BCTreeErrorKind::Init,
-                        ))?;
-
-                trxs_pool.push_back(Box::new(transaction));
-            } else {
-                return Err(Report::new(BlockChainTreeError::BlockChainTree(
-                    BCTreeErrorKind::Init,
-                ))
-                .attach_printable("Not implemented yet"));
+        let mut trxs_pool = Mempool::default();
+        let mut dropped: Vec<[u8; 32]> = Vec::new();
+        for (added_at, transaction) in transactions {
+            let hash = transaction.get_id();
+            if let Err(report) = trxs_pool.add(transaction, added_at) {
+                log::warn!(
+                    "dropping transaction {} of the pool journal: {:?}",
+                    hash.encode_hex::<String>(),
+                    report
+                );
+                dropped.push(hash);
             }
         }
+        dropped.extend(trxs_pool.take_evicted());
+        for transaction in trxs_pool
+            .remove_expired(current_timestamp())
+            .into_iter()
+            .chain(trxs_pool.remove_expired_at_height(main_chain.get_height()))
+        {
+            dropped.push(transaction.get_id());
+        }
 
-        // opening main chain
-        let main_chain = Chain::new()
-            .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))?;
+        for hash in dropped.iter() {
+            pool_journal
+                .append_remove(hash)
+                .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))?;
+        }
+
+        if pool_journal.needs_compaction(trxs_pool.len()) {
+            pool_journal
+                .compact(trxs_pool.iter_with_time())
+                .change_context(BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Init))?;
+        }
 
         Ok(BlockChainTree {
+            root: String::from(root),
             trxs_pool,
+            pool_journal,
+            verifier: BatchVerifier::default(),
             summary_db: Some(summary_db),
             main_chain,
             old_summary_db: Some(old_summary_db),
+            token_ledger,
         })
     }
 
     pub fn without_config() -> Result<BlockChainTree, BlockChainTreeError> {
-        let summary_db_path = Path::new(&AMMOUNT_SUMMARY);
+        BlockChainTree::without_config_at(BLOCKCHAIN_DIRECTORY)
+    }
+
+    /// Creates the tree in the root folder
+    pub fn without_config_at(root: &str) -> Result<BlockChainTree, BlockChainTreeError> {
+        let summary_db_path = String::from(root) + AMMOUNT_SUMMARY;
+        let summary_db_path = Path::new(&summary_db_path);
 
         // open summary db
         let summary_db = sled::open(summary_db_path)
@@ -687,7 +1036,8 @@ impl BlockChainTree {
             ))
             .attach_printable("failed to open summary db")?;
 
-        let old_summary_db_path = Path::new(&OLD_AMMOUNT_SUMMARY);
+        let old_summary_db_path = String::from(root) + OLD_AMMOUNT_SUMMARY;
+        let old_summary_db_path = Path::new(&old_summary_db_path);
 
         // open old summary db
         let old_summary_db = sled::open(old_summary_db_path)
@@ -697,17 +1047,41 @@ impl BlockChainTree {
             ))
             .attach_printable("failed to open old summary db")?;
 
-        // allocate VecDeque
-        let trxs_pool = VecDeque::<Box<dyn Transactionable>>::new();
+        // open token ledger
+        let token_ledger = TokenLedger::open(Path::new(&(String::from(root) + TOKENS_SUMMARY)))
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::InitWithoutConfig,
+            ))?;
+
+        let trxs_pool = Mempool::default();
 
         // opening main chain
-        let main_chain = Chain::new_without_config(MAIN_CHAIN_DIRECTORY, &GENESIS_BLOCK)
+        let main_chain = Chain::new_without_config(
+            &(String::from(root) + MAIN_CHAIN_DIRECTORY),
+            &GENESIS_BLOCK,
+            Network::Main,
+        )
+        .change_context(BlockChainTreeError::BlockChainTree(
+            BCTreeErrorKind::InitWithoutConfig,
+        ))
+        .attach_printable("failed to open main chain")?;
+
+        // start with an empty transactions pool journal
+        let pool_path = String::from(root) + TRANSACTIONS_POOL;
+
+        let mut pool_journal = PoolJournal::open(Path::new(&pool_path)).change_context(
+            BlockChainTreeError::BlockChainTree(BCTreeErrorKind::InitWithoutConfig),
+        )?;
+
+        pool_journal
+            .compact(trxs_pool.iter_with_time())
             .change_context(BlockChainTreeError::BlockChainTree(
                 BCTreeErrorKind::InitWithoutConfig,
-            ))
-            .attach_printable("failed to open main chain")?;
+            ))?;
 
-        let _ = fs::create_dir(Path::new(DERIVATIVE_CHAINS_DIRECTORY));
+        let _ = fs::create_dir(Path::new(
+            &(String::from(root) + DERIVATIVE_CHAINS_DIRECTORY),
+        ));
         // .report()
         // .change_context(BlockChainTreeError::BlockChainTree(
         //     BCTreeErrorKind::CreateDerivChain,
@@ -715,72 +1089,45 @@ impl BlockChainTree {
         // .attach_printable("failed to create root folder for derivatives")?;
 
         Ok(BlockChainTree {
+            root: String::from(root),
             trxs_pool,
+            pool_journal,
+            verifier: BatchVerifier::default(),
             summary_db: Some(summary_db),
             main_chain,
             old_summary_db: Some(old_summary_db),
+            token_ledger,
         })
     }
 
-    pub fn dump_pool(&self) -> Result<(), BlockChainTreeError> {
-        let pool_path = String::from(BLOCKCHAIN_DIRECTORY) + TRANSACTIONS_POOL;
-        let pool_path = Path::new(&pool_path);
-
-        // open file
-        let mut file = File::create(pool_path)
-            .report()
-            .change_context(BlockChainTreeError::BlockChainTree(
-                BCTreeErrorKind::DumpPool,
-            ))
-            .attach_printable("failed to open config file")?;
-
-        // write transactions amount
-        file.write_all(&(self.trxs_pool.len() as u64).to_be_bytes())
-            .report()
+    /// Rewrites transactions pool journal so it only contains transactions of the pool
+    pub fn dump_pool(&mut self) -> Result<(), BlockChainTreeError> {
+        self.pool_journal
+            .compact(self.trxs_pool.iter_with_time())
             .change_context(BlockChainTreeError::BlockChainTree(
                 BCTreeErrorKind::DumpPool,
             ))
-            .attach_printable("failed to write amount of transactions")?;
-
-        //write transactions
-        for transaction in self.trxs_pool.iter() {
-            // get dump
-            let dump = transaction
-                .dump()
-                .change_context(BlockChainTreeError::BlockChainTree(
-                    BCTreeErrorKind::DumpPool,
-                ))?;
-
-            // write transaction size
-            file.write_all(&(dump.len() as u32).to_be_bytes())
-                .report()
-                .change_context(BlockChainTreeError::BlockChainTree(
-                    BCTreeErrorKind::DumpPool,
-                ))
-                .attach_printable("failed to write transaction size")?;
-
-            // write transaction dump
-            file.write_all(&dump)
-                .report()
-                .change_context(BlockChainTreeError::BlockChainTree(
-                    BCTreeErrorKind::DumpPool,
-                ))
-                .attach_printable("failed to write transaction dump")?;
-        }
-
-        Ok(())
     }
 
     pub fn get_derivative_chain(
         &mut self,
         addr: &[u8; 33],
     ) -> Result<Option<Box<DerivativeChain>>, BlockChainTreeError> {
-        let mut path_string = String::from(DERIVATIVE_CHAINS_DIRECTORY);
-        let hex_addr: String = addr.encode_hex::<String>();
-        path_string += &hex_addr;
-        path_string += "/";
-
+        let path_string = derivative_chain_path(&self.root, addr, self.main_chain.get_network());
         let path = Path::new(&path_string);
+
+        // chains used to be named by hex of the address
+        let legacy_path_string = legacy_derivative_chain_path(&self.root, addr);
+        let legacy_path = Path::new(&legacy_path_string);
+        if !path.exists() && legacy_path.exists() {
+            fs::rename(legacy_path, path)
+                .report()
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::GetDerivChain,
+                ))
+                .attach_printable("failed to rename legacy chain folder")?;
+        }
+
         if path.exists() {
             let result = DerivativeChain::new(&path_string).change_context(
                 BlockChainTreeError::BlockChainTree(BCTreeErrorKind::GetDerivChain),
@@ -799,14 +1146,12 @@ impl BlockChainTree {
     }
 
     pub fn create_derivative_chain(
+        &self,
         addr: &[u8; 33],
         genesis_hash: &[u8; 32],
         global_height: u64,
     ) -> Result<Box<DerivativeChain>, BlockChainTreeError> {
-        let mut root_path = String::from(DERIVATIVE_CHAINS_DIRECTORY);
-        let hex_addr: String = addr.encode_hex::<String>();
-        root_path += &hex_addr;
-        root_path += "/";
+        let root_path = derivative_chain_path(&self.root, addr, self.main_chain.get_network());
 
         fs::create_dir(Path::new(&root_path))
             .report()
@@ -845,10 +1190,10 @@ impl BlockChainTree {
         Ok(Box::new(chain))
     }
 
-    pub fn check_main_folders() -> Result<(), BlockChainTreeError> {
-        let root = Path::new(BLOCKCHAIN_DIRECTORY);
-        if !root.exists() {
-            fs::create_dir(root)
+    pub fn check_main_folders(root: &str) -> Result<(), BlockChainTreeError> {
+        let root_path = Path::new(root);
+        if !root_path.exists() {
+            fs::create_dir(root_path)
                 .report()
                 .change_context(BlockChainTreeError::BlockChainTree(
                     BCTreeErrorKind::CheckMainFolders,
@@ -856,7 +1201,8 @@ impl BlockChainTree {
                 .attach_printable("failed to create blockchain root")?;
         }
 
-        let main_path = Path::new(MAIN_CHAIN_DIRECTORY);
+        let main_path = String::from(root) + MAIN_CHAIN_DIRECTORY;
+        let main_path = Path::new(&main_path);
         if !main_path.exists() {
             fs::create_dir(main_path)
                 .report()
@@ -866,7 +1212,8 @@ impl BlockChainTree {
                 .attach_printable("failed to create main chain folder")?;
         }
 
-        let summary_path = Path::new(AMMOUNT_SUMMARY);
+        let summary_path = String::from(root) + AMMOUNT_SUMMARY;
+        let summary_path = Path::new(&summary_path);
         if !summary_path.exists() {
             fs::create_dir(summary_path)
                 .report()
@@ -876,7 +1223,8 @@ impl BlockChainTree {
                 .attach_printable("failed to create summary folder")?;
         }
 
-        let old_summary_path = Path::new(OLD_AMMOUNT_SUMMARY);
+        let old_summary_path = String::from(root) + OLD_AMMOUNT_SUMMARY;
+        let old_summary_path = Path::new(&old_summary_path);
         if !old_summary_path.exists() {
             fs::create_dir(old_summary_path)
                 .report()
@@ -886,7 +1234,18 @@ impl BlockChainTree {
                 .attach_printable("failed to create old summary folder")?;
         }
 
-        let blocks_path = String::from(MAIN_CHAIN_DIRECTORY) + BLOCKS_FOLDER;
+        let tokens_path = String::from(root) + TOKENS_SUMMARY;
+        let tokens_path = Path::new(&tokens_path);
+        if !tokens_path.exists() {
+            fs::create_dir(tokens_path)
+                .report()
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::CheckMainFolders,
+                ))
+                .attach_printable("failed to create tokens folder")?;
+        }
+
+        let blocks_path = String::from(root) + MAIN_CHAIN_DIRECTORY + BLOCKS_FOLDER;
         let blocks_path = Path::new(&blocks_path);
         if !blocks_path.exists() {
             fs::create_dir(blocks_path)
@@ -897,7 +1256,7 @@ impl BlockChainTree {
                 .attach_printable("failed to create blocks path")?;
         }
 
-        let references_path = String::from(MAIN_CHAIN_DIRECTORY) + REFERENCES_FOLDER;
+        let references_path = String::from(root) + MAIN_CHAIN_DIRECTORY + REFERENCES_FOLDER;
         let references_path = Path::new(&references_path);
         if !references_path.exists() {
             fs::create_dir(references_path)
@@ -908,7 +1267,40 @@ impl BlockChainTree {
                 .attach_printable("failed to create references paths")?;
         }
 
-        let derivatives_path = String::from(DERIVATIVE_CHAINS_DIRECTORY);
+        let payloads_path = String::from(root) + MAIN_CHAIN_DIRECTORY + PAYLOADS_FOLDER;
+        let payloads_path = Path::new(&payloads_path);
+        if !payloads_path.exists() {
+            fs::create_dir(payloads_path)
+                .report()
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::CheckMainFolders,
+                ))
+                .attach_printable("failed to create payload references path")?;
+        }
+
+        let transactions_path = String::from(root) + MAIN_CHAIN_DIRECTORY + TRANSACTIONS_FOLDER;
+        let transactions_path = Path::new(&transactions_path);
+        if !transactions_path.exists() {
+            fs::create_dir(transactions_path)
+                .report()
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::CheckMainFolders,
+                ))
+                .attach_printable("failed to create transaction references path")?;
+        }
+
+        let history_path = String::from(root) + MAIN_CHAIN_DIRECTORY + HISTORY_FOLDER;
+        let history_path = Path::new(&history_path);
+        if !history_path.exists() {
+            fs::create_dir(history_path)
+                .report()
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::CheckMainFolders,
+                ))
+                .attach_printable("failed to create address history path")?;
+        }
+
+        let derivatives_path = String::from(root) + DERIVATIVE_CHAINS_DIRECTORY;
         let derivatives_path = Path::new(&derivatives_path);
         if !derivatives_path.exists() {
             fs::create_dir(derivatives_path)
@@ -919,7 +1311,8 @@ impl BlockChainTree {
                 .attach_printable("failed to create derivatives chains path")?;
         }
 
-        let derivative_chains_path = String::from(DERIVATIVE_CHAINS_DIRECTORY) + CHAINS_FOLDER;
+        let derivative_chains_path =
+            String::from(root) + DERIVATIVE_CHAINS_DIRECTORY + CHAINS_FOLDER;
         let derivative_chains_path = Path::new(&derivative_chains_path);
         if !derivative_chains_path.exists() {
             fs::create_dir(derivative_chains_path)
@@ -940,89 +1333,23 @@ impl BlockChainTree {
         addr: &[u8; 33],
         funds: &BigUint,
     ) -> Result<(), BlockChainTreeError> {
-        let result = self.summary_db.as_mut().unwrap().get(addr);
-        match result {
-            Ok(None) => {
-                let mut dump: Vec<u8> = Vec::with_capacity(tools::bigint_size(funds));
-                tools::dump_biguint(funds, &mut dump).change_context(
-                    BlockChainTreeError::BlockChainTree(BCTreeErrorKind::AddFunds),
-                )?;
-
-                self.summary_db
-                    .as_mut()
-                    .unwrap()
-                    .insert(addr, dump)
-                    .report()
-                    .change_context(BlockChainTreeError::BlockChainTree(
-                        BCTreeErrorKind::AddFunds,
-                    ))
-                    .attach_printable(format!(
-                        "failed to create and add funds at address: {}",
-                        std::str::from_utf8(addr).unwrap()
-                    ))?;
-
-                unsafe { self.summary_db.as_mut().unwrap_unchecked() }
-                    .flush_async()
-                    .await
-                    .report()
-                    .change_context(BlockChainTreeError::BlockChainTree(
-                        BCTreeErrorKind::AddFunds,
-                    ))
-                    .attach_printable(format!(
-                        "failed to create and add funds at address: {}",
-                        std::str::from_utf8(addr).unwrap()
-                    ))?;
-
-                Ok(())
-            }
-            Ok(Some(prev)) => {
-                let res = tools::load_biguint(&prev).change_context(
-                    BlockChainTreeError::BlockChainTree(BCTreeErrorKind::AddFunds),
-                )?;
-
-                let mut previous = res.0;
-                previous += funds;
-
-                let mut dump: Vec<u8> = Vec::with_capacity(tools::bigint_size(&previous));
-                tools::dump_biguint(&previous, &mut dump).change_context(
-                    BlockChainTreeError::BlockChainTree(BCTreeErrorKind::AddFunds),
-                )?;
-
-                self.summary_db
-                    .as_mut()
-                    .unwrap()
-                    .insert(addr, dump)
-                    .report()
-                    .change_context(BlockChainTreeError::BlockChainTree(
-                        BCTreeErrorKind::AddFunds,
-                    ))
-                    .attach_printable(format!(
-                        "failed to put funds at address: {}",
-                        std::str::from_utf8(addr).unwrap()
-                    ))?;
+        let previous = self
+            .get_funds(addr)
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddFunds,
+            ))?;
 
-                unsafe { self.summary_db.as_mut().unwrap_unchecked() }
-                    .flush_async()
-                    .await
-                    .report()
-                    .change_context(BlockChainTreeError::BlockChainTree(
-                        BCTreeErrorKind::AddFunds,
-                    ))
-                    .attach_printable(format!(
-                        "failed to create and add funds at address: {}",
-                        std::str::from_utf8(addr).unwrap()
-                    ))?;
+        let new = &previous + funds;
 
-                Ok(())
-            }
-            Err(_) => Err(Report::new(BlockChainTreeError::BlockChainTree(
+        self.set_funds(addr, &new)
+            .await
+            .change_context(BlockChainTreeError::BlockChainTree(
                 BCTreeErrorKind::AddFunds,
             ))
             .attach_printable(format!(
-                "failed to get data from address: {}",
-                std::str::from_utf8(addr).unwrap()
-            ))),
-        }
+                "failed to put funds at address: {}",
+                Address::new(*addr, self.main_chain.get_network())
+            ))
     }
 
     pub async fn decrease_funds(
@@ -1030,69 +1357,536 @@ impl BlockChainTree {
         addr: &[u8; 33],
         funds: &BigUint,
     ) -> Result<(), BlockChainTreeError> {
-        let result = self.summary_db.as_mut().unwrap().get(addr);
-        match result {
-            Ok(None) => Err(Report::new(BlockChainTreeError::BlockChainTree(
+        let previous = self
+            .get_funds(addr)
+            .change_context(BlockChainTreeError::BlockChainTree(
                 BCTreeErrorKind::DecreaseFunds,
-            ))
-            .attach_printable(format!(
-                "address: {} doesn't have any coins",
-                std::str::from_utf8(addr).unwrap()
-            ))),
-            Ok(Some(prev)) => {
-                let res = tools::load_biguint(&prev).change_context(
-                    BlockChainTreeError::BlockChainTree(BCTreeErrorKind::DecreaseFunds),
+            ))?;
+
+        if previous < *funds {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::DecreaseFunds,
+            ))
+            .attach_printable("insufficient balance"));
+        }
+
+        let new = &previous - funds;
+
+        self.set_funds(addr, &new)
+            .await
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::DecreaseFunds,
+            ))
+            .attach_printable(format!(
+                "failed to put funds at address: {}",
+                Address::new(*addr, self.main_chain.get_network())
+            ))
+    }
+
+    /// Writes new balance of the address together with its nonce
+    /// and the updated state tree in one batch
+    async fn set_funds(
+        &mut self,
+        addr: &[u8; 33],
+        new: &BigUint,
+    ) -> Result<(), BlockChainTreeError> {
+        let nonce = self.get_nonce(addr)?;
+        let new = Account::new(new.clone(), nonce);
+
+        let mut tree = self.get_state_tree();
+        tree.insert(addr, &new)
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::UpdateState,
+            ))?;
+
+        let mut batch = sled::Batch::default();
+        BlockChainTree::write_account(&mut batch, addr, &new)?;
+        tree.write(&mut batch);
+
+        let summary_db = self.summary_db.as_ref().unwrap();
+
+        summary_db.apply_batch(batch).report().change_context(
+            BlockChainTreeError::BlockChainTree(BCTreeErrorKind::UpdateState),
+        )?;
+
+        summary_db.flush_async().await.report().change_context(
+            BlockChainTreeError::BlockChainTree(BCTreeErrorKind::UpdateState),
+        )?;
+
+        Ok(())
+    }
+
+    fn write_account(
+        batch: &mut sled::Batch,
+        addr: &[u8; 33],
+        account: &Account,
+    ) -> Result<(), BlockChainTreeError> {
+        let mut dump: Vec<u8> = Vec::with_capacity(tools::bigint_size(&account.balance));
+        tools::dump_biguint(&account.balance, &mut dump).change_context(
+            BlockChainTreeError::BlockChainTree(BCTreeErrorKind::UpdateState),
+        )?;
+        batch.insert(addr as &[u8], dump);
+        batch.insert(&nonce_key(addr) as &[u8], &account.nonce.to_be_bytes());
+
+        Ok(())
+    }
+
+    /// State tree stored in the summary db
+    fn get_state_tree(&self) -> StateTree {
+        StateTree::new(self.summary_db.as_ref().unwrap())
+    }
+
+    /// Root of the current summary state
+    pub fn get_state_root(&self) -> Result<[u8; 32], BlockChainTreeError> {
+        self.get_state_tree()
+            .root()
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::GetStateRoot,
+            ))
+    }
+
+    /// Nonce expected in the next transaction of the address
+    pub fn get_nonce(&self, addr: &[u8; 33]) -> Result<u64, BlockChainTreeError> {
+        let result = self
+            .summary_db
+            .as_ref()
+            .unwrap()
+            .get(nonce_key(addr))
+            .report()
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::GetNonce,
+            ))?;
+
+        match result {
+            None => Ok(0),
+            Some(dump) => {
+                let nonce: [u8; 8] = dump.as_ref().try_into().report().change_context(
+                    BlockChainTreeError::BlockChainTree(BCTreeErrorKind::GetNonce),
                 )?;
+                Ok(u64::from_be_bytes(nonce))
+            }
+        }
+    }
+
+    pub fn get_account(&mut self, addr: &[u8; 33]) -> Result<Account, BlockChainTreeError> {
+        let balance = self.get_funds(addr)?;
+        let nonce = self.get_nonce(addr)?;
+        Ok(Account::new(balance, nonce))
+    }
+
+    /// Key set registered for the multisig address
+    pub fn get_multisig_account(
+        &self,
+        addr: &[u8; 33],
+    ) -> Result<Option<MultisigAccount>, BlockChainTreeError> {
+        let result = self
+            .summary_db
+            .as_ref()
+            .unwrap()
+            .get(multisig_key(addr))
+            .report()
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::Multisig,
+            ))?;
+
+        let dump = match result {
+            None => return Ok(None),
+            Some(dump) => dump,
+        };
+
+        let mut index: usize = 0;
+        let account = MultisigAccount::parse(&dump, &mut index).change_context(
+            BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Multisig),
+        )?;
+        if index != dump.len() {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::Multisig,
+            ))
+            .attach_printable("registered key set is corrupted"));
+        }
+
+        Ok(Some(account))
+    }
 
-                let mut previous = res.0;
-                if previous < *funds {
+    /// Checks spends from multisig accounts against their registered key sets
+    ///
+    /// returns key sets registered by the transactions, address -> key set
+    fn calculate_multisig_registrations(
+        &self,
+        transactions: &[Box<dyn Transactionable>],
+    ) -> Result<HashMap<[u8; 33], MultisigAccount>, BlockChainTreeError> {
+        let mut registrations: HashMap<[u8; 33], MultisigAccount> = HashMap::new();
+
+        for transaction in transactions.iter() {
+            if let Some(registration) = transaction.get_multisig_registration() {
+                let addr = registration.get_account().get_address();
+                if registrations.contains_key(&addr) || self.get_multisig_account(&addr)?.is_some()
+                {
                     return Err(Report::new(BlockChainTreeError::BlockChainTree(
-                        BCTreeErrorKind::DecreaseFunds,
+                        BCTreeErrorKind::Multisig,
                     ))
-                    .attach_printable("insufficient balance"));
+                    .attach_printable("key set of the account is already registered"));
                 }
-                previous -= funds;
+                registrations.insert(addr, registration.get_account().clone());
+            }
 
-                let mut dump: Vec<u8> = Vec::with_capacity(tools::bigint_size(&previous));
-                tools::dump_biguint(&previous, &mut dump).change_context(
-                    BlockChainTreeError::BlockChainTree(BCTreeErrorKind::DecreaseFunds),
-                )?;
+            if let Some(spend) = transaction.get_multisig() {
+                let account = match registrations.get(spend.get_sender()) {
+                    Some(account) => Some(account.clone()),
+                    None => self.get_multisig_account(spend.get_sender())?,
+                };
 
-                self.summary_db
-                    .as_mut()
-                    .unwrap()
-                    .insert(addr, dump)
-                    .report()
-                    .change_context(BlockChainTreeError::BlockChainTree(
-                        BCTreeErrorKind::DecreaseFunds,
+                let account = account.ok_or_else(|| {
+                    Report::new(BlockChainTreeError::BlockChainTree(
+                        BCTreeErrorKind::Multisig,
                     ))
-                    .attach_printable(format!(
-                        "failed to put funds at address: {}",
-                        std::str::from_utf8(addr).unwrap()
-                    ))?;
+                    .attach_printable("key set of the account isn't registered")
+                })?;
 
-                unsafe { self.summary_db.as_mut().unwrap_unchecked() }
-                    .flush_async()
-                    .await
-                    .report()
-                    .change_context(BlockChainTreeError::BlockChainTree(
-                        BCTreeErrorKind::AddFunds,
+                if !spend.is_authorized(&account) {
+                    return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                        BCTreeErrorKind::Multisig,
+                    ))
+                    .attach_printable("not enough signatures of the registered keys"));
+                }
+            }
+        }
+
+        Ok(registrations)
+    }
+
+    /// Calculates accounts touched by transactions
+    ///
+    /// returns map address -> (account before, account after)
+    fn calculate_accounts(
+        &mut self,
+        transactions: &[Box<dyn Transactionable>],
+    ) -> Result<HashMap<[u8; 33], (Account, Account)>, BlockChainTreeError> {
+        let mut accounts: HashMap<[u8; 33], (Account, Account)> = HashMap::new();
+
+        for transaction in transactions.iter() {
+            for addr in [transaction.get_sender(), transaction.get_receiver()] {
+                if !accounts.contains_key(addr) {
+                    let account = self.get_account(addr).change_context(
+                        BlockChainTreeError::BlockChainTree(BCTreeErrorKind::CalculateState),
+                    )?;
+                    accounts.insert(*addr, (account.clone(), account));
+                }
+            }
+
+            let sender = &mut accounts.get_mut(transaction.get_sender()).unwrap().1;
+
+            if let Some(nonce) = transaction.get_nonce() {
+                if nonce != sender.nonce {
+                    return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                        BCTreeErrorKind::CalculateState,
                     ))
                     .attach_printable(format!(
-                        "failed to create and add funds at address: {}",
-                        std::str::from_utf8(addr).unwrap()
-                    ))?;
+                        "wrong nonce: expected {}, got {}",
+                        sender.nonce, nonce
+                    )));
+                }
+                sender.nonce += 1;
+            }
 
-                Ok(())
+            // fees are taken out of circulation
+            if let Some(fee) = transaction.get_fee() {
+                if sender.balance < *fee {
+                    return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                        BCTreeErrorKind::CalculateState,
+                    ))
+                    .attach_printable("insufficient balance to pay fee"));
+                }
+                sender.balance -= fee;
             }
-            Err(_) => Err(Report::new(BlockChainTreeError::BlockChainTree(
-                BCTreeErrorKind::DecreaseFunds,
+
+            let amount = match transaction.get_amount() {
+                Some(amount) => amount,
+                None => continue,
+            };
+
+            if sender.balance < *amount {
+                return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::CalculateState,
+                ))
+                .attach_printable("insufficient balance"));
+            }
+            sender.balance -= amount;
+
+            let receiver = &mut accounts.get_mut(transaction.get_receiver()).unwrap().1;
+            receiver.balance += amount;
+            if !is_balance_storable(&receiver.balance) {
+                return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::CalculateState,
+                ))
+                .attach_printable("balance is too large to be stored"));
+            }
+        }
+
+        Ok(accounts)
+    }
+
+    /// Calculates state root of the summary state after applying transactions
+    pub fn calculate_state_root(
+        &mut self,
+        transactions: &[Box<dyn Transactionable>],
+    ) -> Result<[u8; 32], BlockChainTreeError> {
+        let accounts = self.calculate_accounts(transactions)?;
+
+        let mut tree = self.get_state_tree();
+        for (addr, (_, new)) in accounts.iter() {
+            tree.insert(addr, new)
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::CalculateState,
+                ))?;
+        }
+
+        tree.root()
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::CalculateState,
+            ))
+    }
+
+    /// Applies transactions of the block to the summary state and adds it to the main chain
+    ///
+    /// block is rejected if its state root doesn't match the resulting state
+    pub async fn add_transaction_block(
+        &mut self,
+        mut block: TransactionBlock,
+    ) -> Result<(), BlockChainTreeError> {
+        if !block.check_fee() {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))
+            .attach_printable("block fee doesn't match fees of transactions"));
+        }
+
+        if !block
+            .get_transactions()
+            .iter()
+            .all(|transaction| check_payload_fee(transaction.as_ref()))
+        {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))
+            .attach_printable("fee doesn't cover transaction payload"));
+        }
+
+        // receipts prove inclusion against the root, so it has to match transactions
+        if !block
+            .check_merkle_tree()
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))?
+        {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))
+            .attach_printable("merkle tree root doesn't match transactions"));
+        }
+
+        let info = block.get_default_info();
+
+        // transactions are signed against the previous hash, so the block has to extend the tip
+        let last_hash =
+            self.main_chain
+                .get_last_hash()
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::AddTransactionBlock,
+                ))?;
+        if *info.get_previous_hash() != last_hash {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))
+            .attach_printable("block doesn't extend the last block"));
+        }
+
+        if !self
+            .verifier
+            .verify_batch(block.get_transactions(), info.get_previous_hash())
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))?
+        {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))
+            .attach_printable("wrong signature"));
+        }
+
+        // lock times and expiry heights are checked against the header
+        if info.get_height() != self.main_chain.get_height() {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
             ))
             .attach_printable(format!(
-                "failed to get data from address: {}",
-                std::str::from_utf8(addr).unwrap()
-            ))),
+                "block height {} doesn't match chain height {}",
+                info.get_height(),
+                self.main_chain.get_height()
+            )));
+        }
+
+        if info.get_timestamp() > current_timestamp() + MAX_FUTURE_BLOCK_TIME {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))
+            .attach_printable("block timestamp is too far in the future"));
+        }
+
+        // legacy transactions are only bound to the previous hash, so a copy could be
+        // included next to the original
+        let mut included: HashSet<[u8; 32]> =
+            HashSet::with_capacity(block.get_transactions().len());
+        for transaction in block.get_transactions().iter() {
+            if !included.insert(transaction.get_id()) {
+                return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::AddTransactionBlock,
+                ))
+                .attach_printable("transaction is included twice"));
+            }
+
+            if transaction.get_nonce().is_none() && is_nonce_required(info.get_height()) {
+                return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::AddTransactionBlock,
+                ))
+                .attach_printable("transaction without a nonce"));
+            }
+
+            if let Some(lock_time) = transaction.get_lock_time() {
+                if !is_unlocked(lock_time, info.get_height(), info.get_timestamp()) {
+                    return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                        BCTreeErrorKind::AddTransactionBlock,
+                    ))
+                    .attach_printable(format!("transaction is locked until {}", lock_time)));
+                }
+            }
+
+            if let Some(expiry_height) = transaction.get_expiry_height() {
+                if is_expired(expiry_height, info.get_height()) {
+                    return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                        BCTreeErrorKind::AddTransactionBlock,
+                    ))
+                    .attach_printable(format!("transaction expired at height {}", expiry_height)));
+                }
+            }
+        }
+
+        let token_changes = self
+            .token_ledger
+            .calculate_changes(block.get_transactions())
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))?;
+
+        let registrations = self
+            .calculate_multisig_registrations(block.get_transactions())
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))?;
+
+        let accounts = self
+            .calculate_accounts(block.get_transactions())
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))?;
+
+        let mut tree = self.get_state_tree();
+
+        let mut batch = sled::Batch::default();
+        for (addr, (_, new)) in accounts.iter() {
+            tree.insert(addr, new)
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::AddTransactionBlock,
+                ))?;
+
+            BlockChainTree::write_account(&mut batch, addr, new).change_context(
+                BlockChainTreeError::BlockChainTree(BCTreeErrorKind::AddTransactionBlock),
+            )?;
+        }
+
+        let state_root = tree
+            .root()
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))?;
+        if state_root != *block.get_default_info().get_state_root() {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))
+            .attach_printable("state root mismatch"));
+        }
+        tree.write(&mut batch);
+
+        for (addr, account) in registrations.iter() {
+            let mut dump: Vec<u8> = Vec::with_capacity(account.get_dump_size());
+            account.dump(&mut dump);
+            batch.insert(&multisig_key(addr) as &[u8], dump);
+        }
+
+        let summary_db = self.summary_db.as_ref().unwrap();
+
+        summary_db.apply_batch(batch).report().change_context(
+            BlockChainTreeError::BlockChainTree(BCTreeErrorKind::AddTransactionBlock),
+        )?;
+
+        summary_db.flush_async().await.report().change_context(
+            BlockChainTreeError::BlockChainTree(BCTreeErrorKind::AddTransactionBlock),
+        )?;
+
+        self.token_ledger
+            .apply(&token_changes)
+            .await
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::UpdateTokens,
+            ))?;
+
+        // included transactions don't reserve coins anymore
+        for transaction in block.get_transactions().iter() {
+            let hash = transaction.get_id();
+            self.trxs_pool.remove(&hash);
+
+            self.pool_journal.append_remove(&hash).change_context(
+                BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Journal),
+            )?;
+        }
+
+        // transactions conflicting with the committed nonces can't be included anymore
+        for (addr, (_, new)) in accounts.iter() {
+            for transaction in self.trxs_pool.remove_below_nonce(addr, new.nonce) {
+                self.pool_journal
+                    .append_remove(&transaction.get_id())
+                    .change_context(BlockChainTreeError::BlockChainTree(
+                        BCTreeErrorKind::Journal,
+                    ))?;
+            }
+        }
+
+        // transactions that don't fit into the next block anymore
+        let next_height = self.main_chain.get_height() + 1;
+        for transaction in self.trxs_pool.remove_expired_at_height(next_height) {
+            self.pool_journal
+                .append_remove(&transaction.get_id())
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::Journal,
+                ))?;
         }
+
+        if self.pool_journal.needs_compaction(self.trxs_pool.len()) {
+            self.pool_journal
+                .compact(self.trxs_pool.iter_with_time())
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::Journal,
+                ))?;
+        }
+
+        self.main_chain
+            .add_block(&SumTransactionBlock::new(Some(block), None))
+            .await
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::AddTransactionBlock,
+            ))?;
+
+        Ok(())
     }
 
     pub fn get_funds(&mut self, addr: &[u8; 33]) -> Result<BigUint, BlockChainTreeError> {
@@ -1112,7 +1906,7 @@ impl BlockChainTree {
             ))
             .attach_printable(format!(
                 "failed to get data from summary db at address: {}",
-                std::str::from_utf8(addr).unwrap()
+                Address::new(*addr, self.main_chain.get_network())
             ))),
         }
     }
@@ -1135,8 +1929,10 @@ impl BlockChainTree {
     }
 
     pub fn move_summary_database(&mut self) -> Result<(), BlockChainTreeError> {
-        let old_sum_path = Path::new(OLD_AMMOUNT_SUMMARY);
-        let sum_path = Path::new(AMMOUNT_SUMMARY);
+        let old_sum_path = self.root.clone() + OLD_AMMOUNT_SUMMARY;
+        let old_sum_path = Path::new(&old_sum_path);
+        let sum_path = self.root.clone() + AMMOUNT_SUMMARY;
+        let sum_path = Path::new(&sum_path);
 
         self.old_summary_db = None;
         self.summary_db = None;
@@ -1183,58 +1979,447 @@ impl BlockChainTree {
         Ok(())
     }
 
+    /// Nonce expected in the next transaction of the address,
+    /// taking into account transactions waiting in the pool
+    pub fn get_pending_nonce(&self, addr: &[u8; 33]) -> Result<u64, BlockChainTreeError> {
+        let nonce = self.get_nonce(addr)?;
+
+        let mut pending_nonce = nonce;
+        while self.trxs_pool.find_by_nonce(addr, pending_nonce).is_some() {
+            pending_nonce += 1;
+        }
+
+        Ok(pending_nonce)
+    }
+
+    /// Committed balance of the address minus coins reserved by its pending transactions
+    pub fn get_spendable_funds(&mut self, addr: &[u8; 33]) -> Result<BigUint, BlockChainTreeError> {
+        let funds = self.get_funds(addr)?;
+        let pending = self.trxs_pool.get_pending_spent(addr);
+
+        if funds < pending {
+            return Ok(Zero::zero());
+        }
+
+        Ok(funds - pending)
+    }
+
+    /// Whether address holds funds, ever sent a transaction or owns a derivative chain
+    pub fn is_address_used(&mut self, addr: &[u8; 33]) -> Result<bool, BlockChainTreeError> {
+        if !self.get_funds(addr)?.is_zero() || self.get_nonce(addr)? > 0 {
+            return Ok(true);
+        }
+
+        let network = self.main_chain.get_network();
+        Ok(
+            Path::new(&derivative_chain_path(&self.root, addr, network)).exists()
+                || Path::new(&legacy_derivative_chain_path(&self.root, addr)).exists(),
+        )
+    }
+
+    /// Drops transactions that stayed in the pool for too long
+    ///
+    /// returns amount of removed transactions
+    pub fn remove_expired_transactions(&mut self) -> Result<usize, BlockChainTreeError> {
+        let removed = self.trxs_pool.remove_expired(current_timestamp());
+
+        for transaction in removed.iter() {
+            self.pool_journal
+                .append_remove(&transaction.get_id())
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::Journal,
+                ))?;
+        }
+
+        Ok(removed.len())
+    }
+
     pub async fn new_transaction(&mut self, tr: Transaction) -> Result<(), BlockChainTreeError> {
-        // if it is in first bunch of transactions
-        // to be added to blockchain.
-        // AND if it is not a last block
-        // that is pending.
-        if self.trxs_pool.len() < MAX_TRANSACTIONS_PER_BLOCK
-            && self.main_chain.get_height() as usize + 1 % BLOCKS_PER_ITERATION != 0
-        {
-            self.decrease_funds(tr.get_sender(), tr.get_amount())
-                .await
+        self.new_transactionable(Box::new(tr)).await
+    }
+
+    /// Verifies transaction of any kind and adds it to the pool
+    ///
+    /// transaction with the nonce of a pending one replaces it, see `Mempool::replace`
+    pub async fn new_transactionable(
+        &mut self,
+        tr: Box<dyn Transactionable>,
+    ) -> Result<(), BlockChainTreeError> {
+        self.remove_expired_transactions()?;
+
+        if !check_payload_fee(tr.as_ref()) {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::NewTransaction,
+            ))
+            .attach_printable("fee doesn't cover transaction payload"));
+        }
+
+        // transaction reusing a pending nonce replaces the pending one
+        let replaced_hash = tr
+            .get_nonce()
+            .and_then(|nonce| self.trxs_pool.find_by_nonce(tr.get_sender(), nonce))
+            .copied();
+
+        if let (Some(nonce), None) = (tr.get_nonce(), replaced_hash) {
+            let expected = self.get_pending_nonce(tr.get_sender()).change_context(
+                BlockChainTreeError::BlockChainTree(BCTreeErrorKind::NewTransaction),
+            )?;
+
+            if nonce != expected {
+                return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::NewTransaction,
+                ))
+                .attach_printable(format!("wrong nonce: expected {}, got {}", expected, nonce)));
+            }
+        }
+
+        // transaction has to fit into the next block
+        if tr.get_nonce().is_none() && is_nonce_required(self.main_chain.get_height()) {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::NewTransaction,
+            ))
+            .attach_printable("transaction without a nonce"));
+        }
+
+        if let Some(lock_time) = tr.get_lock_time() {
+            if !is_unlocked(lock_time, self.main_chain.get_height(), current_timestamp()) {
+                return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::NewTransaction,
+                ))
+                .attach_printable(format!("transaction is locked until {}", lock_time)));
+            }
+        }
+
+        if let Some(expiry_height) = tr.get_expiry_height() {
+            if is_expired(expiry_height, self.main_chain.get_height()) {
+                return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::NewTransaction,
+                ))
+                .attach_printable(format!("transaction expired at height {}", expiry_height)));
+            }
+        }
+
+        // token entries have to be valid against the committed token states
+        self.token_ledger
+            .calculate_changes(std::slice::from_ref(&tr))
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::NewTransaction,
+            ))?;
+
+        // multisig spends need a committed key set
+        self.calculate_multisig_registrations(std::slice::from_ref(&tr))
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::NewTransaction,
+            ))?;
+
+        let last_hash =
+            self.main_chain
+                .get_last_hash()
                 .change_context(BlockChainTreeError::BlockChainTree(
                     BCTreeErrorKind::NewTransaction,
                 ))?;
 
-            self.add_funds(tr.get_sender(), tr.get_amount())
-                .await
+        if !self
+            .verifier
+            .verify(tr.as_ref(), &last_hash)
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::NewTransaction,
+            ))?
+        {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::NewTransaction,
+            ))
+            .attach_printable("wrong signature"));
+        }
+
+        // coins are only reserved here, summary db is updated
+        // when the block containing transaction is applied
+        let mut spendable = self.get_spendable_funds(tr.get_sender()).change_context(
+            BlockChainTreeError::BlockChainTree(BCTreeErrorKind::NewTransaction),
+        )?;
+        if let Some(replaced) = replaced_hash.and_then(|hash| self.trxs_pool.get(&hash)) {
+            spendable += spent_amount(replaced);
+        }
+
+        if spendable < spent_amount(tr.as_ref()) {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::NewTransaction,
+            ))
+            .attach_printable("insufficient balance"));
+        }
+
+        let now = current_timestamp();
+        let hash = if replaced_hash.is_some() {
+            let (hash, replaced) = self.trxs_pool.replace(tr, now).change_context(
+                BlockChainTreeError::BlockChainTree(BCTreeErrorKind::NewTransaction),
+            )?;
+
+            self.pool_journal
+                .append_remove(&replaced.get_id())
                 .change_context(BlockChainTreeError::BlockChainTree(
-                    BCTreeErrorKind::NewTransaction,
+                    BCTreeErrorKind::Journal,
                 ))?;
+
+            hash
+        } else {
+            self.trxs_pool
+                .add(tr, now)
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::NewTransaction,
+                ))?
+        };
+
+        for evicted in self.trxs_pool.take_evicted() {
+            self.pool_journal.append_remove(&evicted).change_context(
+                BlockChainTreeError::BlockChainTree(BCTreeErrorKind::Journal),
+            )?;
         }
 
-        self.trxs_pool.push_front(Box::new(tr));
+        self.pool_journal
+            .append_add(self.trxs_pool.get(&hash).unwrap(), now)
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::Journal,
+            ))?;
+
         Ok(())
     }
 
-    pub fn pop_last_transactions(&mut self) -> Option<Vec<Box<dyn Transactionable>>> {
+    pub fn pop_last_transactions(
+        &mut self,
+    ) -> Result<Option<Vec<Box<dyn Transactionable>>>, BlockChainTreeError> {
         if self.trxs_pool.is_empty() {
-            return None;
+            return Ok(None);
         }
 
-        let mut transactions_amount = MAX_TRANSACTIONS_PER_BLOCK;
-        if transactions_amount > self.trxs_pool.len() {
-            transactions_amount = self.trxs_pool.len();
+        let transactions = self.trxs_pool.pop_best(MAX_TRANSACTIONS_PER_BLOCK);
+
+        for transaction in transactions.iter() {
+            self.pool_journal
+                .append_remove(&transaction.get_id())
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::Journal,
+                ))?;
+        }
+
+        Ok(Some(transactions))
+    }
+
+    pub fn get_pool(&mut self) -> &Mempool {
+        &self.trxs_pool
+    }
+
+    /// Recommended fee rate for the transaction to be included within `target_blocks`
+    ///
+    /// combines fee rates of the pool with the lowest fee rates of the last blocks
+    pub fn estimate_fee(&self, target_blocks: usize) -> Result<BigUint, BlockChainTreeError> {
+        let height = self.main_chain.get_height();
+
+        let mut block_rates: Vec<BigUint> = Vec::new();
+        for height in (height.saturating_sub(FEE_HISTORY_BLOCKS)..height).rev() {
+            let block = self.get_transaction_block(height).change_context(
+                BlockChainTreeError::BlockChainTree(BCTreeErrorKind::EstimateFee),
+            )?;
+
+            if let Some(rate) = block.as_ref().and_then(block_min_fee_rate) {
+                block_rates.push(rate);
+            }
+        }
+
+        Ok(estimate_fee_rate(
+            &self.trxs_pool.get_fee_rates(),
+            &block_rates,
+            target_blocks,
+            MAX_TRANSACTIONS_PER_BLOCK,
+        ))
+    }
+
+    /// Transaction block of the main chain at the given height
+    fn get_transaction_block(
+        &self,
+        height: u64,
+    ) -> Result<Option<TransactionBlock>, BlockChainTreeError> {
+        Ok(self
+            .main_chain
+            .find_by_height(height)?
+            .and_then(|block| block.into_transaction_block()))
+    }
+
+    /// Committed state of the token
+    pub fn get_token_state(
+        &self,
+        token_hash: &[u8; 32],
+    ) -> Result<Option<TokenState>, BlockChainTreeError> {
+        self.token_ledger
+            .get(token_hash)
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::GetToken,
+            ))
+    }
+
+    /// Hashes of the tokens owned by the address
+    pub fn get_owned_tokens(&self, owner: &[u8; 33]) -> Result<Vec<[u8; 32]>, BlockChainTreeError> {
+        self.token_ledger
+            .get_by_owner(owner)
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::GetToken,
+            ))
+    }
+
+    /// Transaction included in the main chain
+    pub fn get_transaction(
+        &self,
+        key: &[u8; 32],
+    ) -> Result<Option<Box<dyn Transactionable>>, BlockChainTreeError> {
+        let (height, position) = match self.main_chain.find_transaction(key).change_context(
+            BlockChainTreeError::BlockChainTree(BCTreeErrorKind::GetTransaction),
+        )? {
+            Some(location) => location,
+            None => return Ok(None),
+        };
+
+        let block = self.get_transaction_block(height).change_context(
+            BlockChainTreeError::BlockChainTree(BCTreeErrorKind::GetTransaction),
+        )?;
+
+        Ok(block.and_then(|block| block.into_transactions().into_iter().nth(position)))
+    }
+
+    /// Page of transactions sent from or to the address
+    ///
+    /// pending transactions go first, newest first, followed by
+    /// included ones from the last block to the first,
+    /// empty pages are rejected as they can't move the cursor
+    pub fn address_history(
+        &self,
+        addr: &[u8; 33],
+        cursor: HistoryCursor,
+        limit: usize,
+    ) -> Result<HistoryPage, BlockChainTreeError> {
+        if limit == 0 {
+            return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::GetHistory,
+            ))
+            .attach_printable("page limit can't be zero"));
         }
-        let mut to_return: Vec<Box<dyn Transactionable>> = Vec::with_capacity(transactions_amount);
 
-        let mut counter = 0;
+        let mut entries: Vec<HistoryEntry> = Vec::with_capacity(limit);
+
+        let before = match cursor {
+            HistoryCursor::Pending(skip) => {
+                let pending: Vec<&dyn Transactionable> = self
+                    .trxs_pool
+                    .iter_with_time()
+                    .map(|(transaction, _)| transaction)
+                    .filter(|transaction| {
+                        transaction.get_sender() == addr || transaction.get_receiver() == addr
+                    })
+                    .collect();
+
+                for transaction in pending.iter().rev().skip(skip).take(limit) {
+                    entries.push(HistoryEntry::new(transaction.clone_box(), None));
+                }
+
+                if skip + entries.len() < pending.len() || entries.len() == limit {
+                    let next = HistoryCursor::Pending(skip + entries.len());
+                    return Ok(HistoryPage::new(entries, Some(next)));
+                }
+                None
+            }
+            HistoryCursor::Included(height, position) => Some((height, position)),
+        };
+
+        let locations = self
+            .main_chain
+            .find_address_history(addr, before, limit - entries.len())
+            .change_context(BlockChainTreeError::BlockChainTree(
+                BCTreeErrorKind::GetHistory,
+            ))?;
 
-        while counter < transactions_amount {
-            let result = self.trxs_pool.pop_back();
-            if result.is_none() {
-                break;
+        // transactions of the last loaded block
+        let mut loaded_height: Option<u64> = None;
+        let mut transactions: Vec<Option<Box<dyn Transactionable>>> = Vec::new();
+        for (height, position) in locations.iter() {
+            if loaded_height != Some(*height) {
+                transactions = self
+                    .get_transaction_block(*height)
+                    .change_context(BlockChainTreeError::BlockChainTree(
+                        BCTreeErrorKind::GetHistory,
+                    ))?
+                    .ok_or_else(|| {
+                        Report::new(BlockChainTreeError::BlockChainTree(
+                            BCTreeErrorKind::GetHistory,
+                        ))
+                        .attach_printable(format!("indexed block {} is missing", height))
+                    })?
+                    .into_transactions()
+                    .into_iter()
+                    .map(Some)
+                    .collect();
+                loaded_height = Some(*height);
             }
-            let tr = result.unwrap();
 
-            to_return.push(tr);
-            counter += 1;
+            let transaction = transactions
+                .get_mut(*position)
+                .and_then(|transaction| transaction.take())
+                .ok_or_else(|| {
+                    Report::new(BlockChainTreeError::BlockChainTree(
+                        BCTreeErrorKind::GetHistory,
+                    ))
+                    .attach_printable(format!(
+                        "indexed transaction {} of block {} is missing",
+                        position, height
+                    ))
+                })?;
+
+            entries.push(HistoryEntry::new(transaction, Some((*height, *position))));
         }
-        Some(to_return)
+
+        let next = match locations.last() {
+            Some((height, position)) if entries.len() == limit => {
+                Some(HistoryCursor::Included(*height, *position))
+            }
+            _ => None,
+        };
+
+        Ok(HistoryPage::new(entries, next))
     }
 
-    pub fn get_pool(&mut self) -> &VecDeque<Box<dyn Transactionable>> {
-        &self.trxs_pool
+    /// Receipt of the transaction included in the main chain
+    pub fn get_receipt(&self, key: &[u8; 32]) -> Result<Option<Receipt>, BlockChainTreeError> {
+        let (height, position) = match self.main_chain.find_transaction(key).change_context(
+            BlockChainTreeError::BlockChainTree(BCTreeErrorKind::GetReceipt),
+        )? {
+            Some(location) => location,
+            None => return Ok(None),
+        };
+
+        let mut block = match self.get_transaction_block(height).change_context(
+            BlockChainTreeError::BlockChainTree(BCTreeErrorKind::GetReceipt),
+        )? {
+            Some(block) => block,
+            None => {
+                return Err(Report::new(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::GetReceipt,
+                ))
+                .attach_printable(format!("indexed block {} is missing", height)));
+            }
+        };
+
+        let proof =
+            block
+                .get_proof(position)
+                .change_context(BlockChainTreeError::BlockChainTree(
+                    BCTreeErrorKind::GetReceipt,
+                ))?;
+
+        let confirmations = self.main_chain.get_height() - height;
+
+        Ok(Some(Receipt::new(
+            block,
+            height,
+            position,
+            confirmations,
+            proof,
+        )))
     }
 }
@@ -8,6 +8,11 @@ pub enum Headers {
     TransactionBlock = 2,
     TokenBlock = 3,
     SummarizeBlock = 4,
+    VersionedTransaction = 5,
+    MultisigTransaction = 6,
+    MemoTransaction = 7,
+    TokenAction = 8,
+    MultisigRegistration = 9,
 }
 
 impl Headers {
@@ -18,6 +23,11 @@ impl Headers {
             2 => Ok(Headers::TransactionBlock),
             3 => Ok(Headers::TokenBlock),
             4 => Ok(Headers::SummarizeBlock),
+            5 => Ok(Headers::VersionedTransaction),
+            6 => Ok(Headers::MultisigTransaction),
+            7 => Ok(Headers::MemoTransaction),
+            8 => Ok(Headers::TokenAction),
+            9 => Ok(Headers::MultisigRegistration),
             _ => Err(Report::new(DumpHeadersError::DumpHeadersError(
                 DumpHeadersErrorKind::UknownHeader,
             ))),
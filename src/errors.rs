@@ -87,6 +87,30 @@ root_errors![
 
     DumpHeadersError : "Error with dump header"{
         DumpHeadersError(DumpHeadersErrorKind)
+    },
+
+    MempoolError : "Error ocurred while operating on the transactions pool" {
+        Mempool(MempoolErrorKind)
+    },
+
+    PoolJournalError : "Error ocurred while operating on the transactions pool journal" {
+        Journal(PoolJournalErrorKind)
+    },
+
+    WalletError : "Error ocurred while operating on the wallet" {
+        Wallet(WalletErrorKind)
+    },
+
+    AddressError : "Error ocurred while operating on an address" {
+        Address(AddressErrorKind)
+    },
+
+    TokenError : "Error ocurred while operating on a token" {
+        Token(TokenErrorKind)
+    },
+
+    StateError : "Error ocurred while operating on the summary state" {
+        State(StateErrorKind)
     }
 ];
 
@@ -109,7 +133,41 @@ sub_errors![
     TxErrorKind {
         Verify: "failed to verify transaction",
         Dump: "failed to dump transaction (amount)",
-        Parse: "failed to parse transaction"
+        Parse: "failed to parse transaction",
+        Multisig: "failed to operate on multisig account",
+        Payload: "wrong transaction payload",
+        Unregistered: "transaction kind is not registered"
+    },
+    MempoolErrorKind {
+        Duplicate: "transaction is already in the pool",
+        Conflict: "transaction conflicts with a pending one",
+        PoolIsFull: "pool is full",
+        Replacement: "transaction can't replace the pending one",
+        Dump: "failed to dump transaction"
+    },
+    PoolJournalErrorKind {
+        Open: "failed to open journal",
+        Append: "failed to append record to journal",
+        Replay: "failed to replay journal",
+        Compact: "failed to compact journal"
+    },
+    AddressErrorKind {
+        Parse: "failed to parse address",
+        Network: "unknown network",
+        Checksum: "wrong checksum",
+        Length: "wrong length of the key"
+    },
+    WalletErrorKind {
+        InvalidKey: "invalid secret key",
+        UnknownAddress: "no key for the address",
+        Sign: "failed to sign transaction",
+        Encrypt: "failed to encrypt keystore",
+        Decrypt: "failed to decrypt keystore",
+        Save: "failed to save keystore",
+        Load: "failed to load keystore",
+        Mnemonic: "invalid mnemonic phrase",
+        Derive: "failed to derive key",
+        Recover: "failed to recover keys"
     },
     MerkleTreeErrorKind {
         GettingProof: "failed to get proof"
@@ -125,6 +183,7 @@ sub_errors![
     },
     TxBlockErrorKind {
         BuildingMerkleTree: "failed to build merkle tree",
+        GettingProof: "failed to get merkle proof",
         Dump: "failed to dump",
         Parse: "failed to parse"
     },
@@ -142,6 +201,8 @@ sub_errors![
         AddingBlock: "failed to add block",
         FindByHeight: "failed to find block by height",
         FindByHashE: "failed to find by hash",
+        FindTransaction: "failed to find transaction",
+        FindHistory: "failed to find address history",
         DumpConfig: "failed to dump config",
         InitWithoutConfig: "failed to create a new chain without config"
     },
@@ -165,6 +226,32 @@ sub_errors![
         GetFunds: "failed to get funds",
         GetOldFunds: "failed to get funds from old summary db",
         MoveSummaryDB: "failed to move summary database",
-        NewTransaction: "failed to create new transaction"
+        NewTransaction: "failed to create new transaction",
+        UpdateState: "failed to update summary state",
+        GetStateRoot: "failed to get state root",
+        GetNonce: "failed to get nonce",
+        CalculateState: "failed to calculate summary state",
+        AddTransactionBlock: "failed to add transaction block",
+        Journal: "failed to update transactions pool journal",
+        GetTransaction: "failed to get transaction",
+        GetReceipt: "failed to get receipt",
+        GetHistory: "failed to get address history",
+        EstimateFee: "failed to estimate fee",
+        GetToken: "failed to get token state",
+        UpdateTokens: "failed to update token ledger",
+        Multisig: "failed to check multisig account"
+    },
+    TokenErrorKind {
+        Creation: "failed to create token",
+        Verify: "failed to verify token",
+        Dump: "failed to dump token",
+        Parse: "failed to parse token",
+        Action: "token action is not allowed",
+        Ledger: "failed to access token ledger",
+        NotImplemented: "not implemented yet"
+    },
+    StateErrorKind {
+        Leaf: "failed to build account leaf",
+        Storage: "failed to access state tree storage"
     }
 ];
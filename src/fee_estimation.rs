@@ -0,0 +1,66 @@
+use crate::block::TransactionBlock;
+use crate::mempool::fee_rate;
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+// Fee rates are fees paid per 1000 bytes of the transaction dump
+//
+// pool estimate is the rate outbidding the last transaction that fits
+// into the target amount of blocks, history estimate is the lowest rate
+// included by recent blocks, taken at a lower percentile for further targets
+
+/// Amount of the last transaction blocks fee statistics is collected from
+pub static FEE_HISTORY_BLOCKS: u64 = 20;
+/// Lowest recommended fee rate
+pub static MIN_FEE_RATE: u64 = 1;
+
+/// Lowest fee rate of the fee paying transfers included in the block
+///
+/// token entries and transfers without a fee don't compete for space,
+/// so they would drag the estimate down to zero
+pub fn block_min_fee_rate(block: &TransactionBlock) -> Option<BigUint> {
+    block
+        .get_transactions()
+        .iter()
+        .filter(|transaction| {
+            transaction.get_amount().is_some()
+                && transaction.get_fee().is_some_and(|fee| !fee.is_zero())
+        })
+        .map(|transaction| fee_rate(transaction.as_ref()))
+        .min()
+}
+
+/// Fee the transaction of the given dump size has to pay to reach the fee rate
+pub fn fee_for_size(fee_rate: &BigUint, size: usize) -> BigUint {
+    (fee_rate * size + 999u32) / 1000u32
+}
+
+/// Recommended fee rate for the transaction to be included within `target_blocks`
+///
+/// `pool_rates` are fee rates of pending transactions from the highest,
+/// `block_rates` are lowest fee rates of the recent blocks
+pub fn estimate_fee_rate(
+    pool_rates: &[BigUint],
+    block_rates: &[BigUint],
+    target_blocks: usize,
+    block_capacity: usize,
+) -> BigUint {
+    let target_blocks = target_blocks.max(1);
+    let mut estimate = BigUint::from(MIN_FEE_RATE);
+
+    // transactions ahead of the new one in the pool
+    let capacity = target_blocks.saturating_mul(block_capacity);
+    if capacity > 0 && pool_rates.len() >= capacity {
+        let outbid = &pool_rates[capacity - 1] + 1u32;
+        estimate = estimate.max(outbid);
+    }
+
+    if !block_rates.is_empty() {
+        let mut block_rates = block_rates.to_vec();
+        block_rates.sort();
+        let index = block_rates.len() / (target_blocks + 1);
+        estimate = estimate.max(block_rates[index].clone());
+    }
+
+    estimate
+}
@@ -0,0 +1,154 @@
+use crate::errors::*;
+use crate::wallet::address_from_secret_key;
+use hmac::{Hmac, Mac, NewMac};
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use sha2::Sha512;
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+// BIP32 derivation of private keys
+//
+// child key is IL + parent key, where IL || chain code = HMAC-SHA512(parent chain code, data),
+// data is 0x00 || parent key || index for hardened indexes
+// and compressed parent public key || index otherwise
+
+pub static HARDENED: u32 = 0x8000_0000;
+
+/// Key of the master node is HMAC-SHA512 of the seed with this key
+static MASTER_KEY: &[u8] = b"Bitcoin seed";
+
+/// Coin type in the BIP44 path m/44'/coin'/account'/change/index
+pub static COIN_TYPE: u32 = 9119;
+
+/// Amount of unused addresses in a row after which recovery stops looking
+pub static GAP_LIMIT: u32 = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedKey {
+    secret_key: [u8; 32],
+    chain_code: [u8; 32],
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).unwrap();
+    mac.update(data);
+
+    let mut result = [0u8; 64];
+    result.copy_from_slice(&mac.finalize().into_bytes());
+    result
+}
+
+/// Path of the external address with the given index of the first account
+pub fn address_path(index: u32) -> [u32; 5] {
+    [44 | HARDENED, COIN_TYPE | HARDENED, HARDENED, 0, index]
+}
+
+/// Parses derivation path like m/44'/9119'/0'/0/1
+pub fn parse_path(path: &str) -> Result<Vec<u32>, WalletError> {
+    let mut parts = path.split('/');
+    if parts.next() != Some("m") {
+        return Err(Report::new(WalletError::Wallet(WalletErrorKind::Derive))
+            .attach_printable("path should start with m"));
+    }
+
+    let mut indexes: Vec<u32> = Vec::new();
+    for part in parts {
+        let (number, hardened) = match part.strip_suffix('\'') {
+            Some(number) => (number, true),
+            None => (part, false),
+        };
+
+        let index: u32 = number
+            .parse()
+            .report()
+            .change_context(WalletError::Wallet(WalletErrorKind::Derive))
+            .attach_printable(format!("wrong index {}", part))?;
+
+        if index >= HARDENED {
+            return Err(Report::new(WalletError::Wallet(WalletErrorKind::Derive))
+                .attach_printable(format!("index {} is too large", part)));
+        }
+
+        indexes.push(if hardened { index | HARDENED } else { index });
+    }
+
+    Ok(indexes)
+}
+
+impl ExtendedKey {
+    /// Master key of the seed
+    pub fn from_seed(seed: &[u8]) -> Result<ExtendedKey, WalletError> {
+        let digest = hmac_sha512(MASTER_KEY, seed);
+
+        let mut secret_key = [0u8; 32];
+        let mut chain_code = [0u8; 32];
+        secret_key.copy_from_slice(&digest[..32]);
+        chain_code.copy_from_slice(&digest[32..]);
+
+        SecretKey::from_slice(&secret_key)
+            .report()
+            .change_context(WalletError::Wallet(WalletErrorKind::Derive))
+            .attach_printable("seed produces invalid master key")?;
+
+        Ok(ExtendedKey {
+            secret_key,
+            chain_code,
+        })
+    }
+
+    pub fn get_secret_key(&self) -> &[u8; 32] {
+        &self.secret_key
+    }
+
+    pub fn get_chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    pub fn get_address(&self) -> Result<[u8; 33], WalletError> {
+        address_from_secret_key(&self.secret_key)
+    }
+
+    pub fn derive_child(&self, index: u32) -> Result<ExtendedKey, WalletError> {
+        let parent = SecretKey::from_slice(&self.secret_key)
+            .report()
+            .change_context(WalletError::Wallet(WalletErrorKind::Derive))?;
+
+        let mut data: Vec<u8> = Vec::with_capacity(37);
+        if index & HARDENED != 0 {
+            data.push(0);
+            data.extend(self.secret_key);
+        } else {
+            data.extend(
+                PublicKey::from_secret_key(&Secp256k1::signing_only(), &parent).serialize(),
+            );
+        }
+        data.extend(index.to_be_bytes());
+
+        let digest = hmac_sha512(&self.chain_code, &data);
+
+        let mut child = parent;
+        child
+            .add_assign(&digest[..32])
+            .report()
+            .change_context(WalletError::Wallet(WalletErrorKind::Derive))
+            .attach_printable(format!("index {} produces invalid key", index))?;
+
+        let mut secret_key = [0u8; 32];
+        let mut chain_code = [0u8; 32];
+        secret_key.copy_from_slice(&child[..]);
+        chain_code.copy_from_slice(&digest[32..]);
+
+        Ok(ExtendedKey {
+            secret_key,
+            chain_code,
+        })
+    }
+
+    pub fn derive_path(&self, path: &[u32]) -> Result<ExtendedKey, WalletError> {
+        let mut key = self.clone();
+        for index in path.iter() {
+            key = key.derive_child(*index)?;
+        }
+        Ok(key)
+    }
+}
@@ -0,0 +1,75 @@
+use crate::transaction::Transactionable;
+
+/// Position in the history of the address
+///
+/// history starts with pending transactions, newest first,
+/// followed by included ones from the last block to the first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryCursor {
+    /// amount of pending transactions already returned
+    Pending(usize),
+    /// height and position of the last returned included transaction
+    Included(u64, usize),
+}
+
+impl Default for HistoryCursor {
+    fn default() -> Self {
+        HistoryCursor::Pending(0)
+    }
+}
+
+#[derive(Debug)]
+pub struct HistoryEntry {
+    transaction: Box<dyn Transactionable>,
+    location: Option<(u64, usize)>,
+}
+
+impl HistoryEntry {
+    pub fn new(
+        transaction: Box<dyn Transactionable>,
+        location: Option<(u64, usize)>,
+    ) -> HistoryEntry {
+        HistoryEntry {
+            transaction,
+            location,
+        }
+    }
+
+    pub fn get_transaction(&self) -> &dyn Transactionable {
+        self.transaction.as_ref()
+    }
+
+    /// Height of the block and position in it, None for pending transactions
+    pub fn get_location(&self) -> Option<(u64, usize)> {
+        self.location
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.location.is_none()
+    }
+}
+
+#[derive(Debug)]
+pub struct HistoryPage {
+    entries: Vec<HistoryEntry>,
+    next: Option<HistoryCursor>,
+}
+
+impl HistoryPage {
+    pub fn new(entries: Vec<HistoryEntry>, next: Option<HistoryCursor>) -> HistoryPage {
+        HistoryPage { entries, next }
+    }
+
+    pub fn get_entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn into_entries(self) -> Vec<HistoryEntry> {
+        self.entries
+    }
+
+    /// Cursor of the next page, None if this page is the last one
+    pub fn get_next(&self) -> Option<HistoryCursor> {
+        self.next
+    }
+}
@@ -1,9 +1,24 @@
 #![allow(dead_code)]
 #![allow(unused_variables)]
+pub mod address;
 pub mod block;
 pub mod blockchaintree;
 pub mod dump_headers;
 pub mod errors;
+pub mod fee_estimation;
+pub mod hd;
+pub mod history;
+pub mod memo;
+pub mod mempool;
 pub mod merkletree;
+pub mod multisig;
+pub mod pool_journal;
+pub mod receipt;
+pub mod registry;
+pub mod state;
+pub mod token;
+pub mod token_ledger;
 pub mod tools;
 pub mod transaction;
+pub mod verifier;
+pub mod wallet;
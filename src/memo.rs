@@ -0,0 +1,352 @@
+use crate::dump_headers::Headers;
+use crate::errors::*;
+use crate::tools;
+use crate::transaction::Transactionable;
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::convert::TryInto;
+
+use secp256k1::ecdsa::Signature;
+use secp256k1::PublicKey;
+use secp256k1::{Message, Secp256k1, SecretKey, VerifyOnly};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+/*
+    Memo transaction dumping protocol
+
+    Header (Headers::MemoTransaction) - 1 byte
+    Sender - 33 bytes
+    Receiver - 33 bytes
+    Timestamp - 8 bytes
+    Nonce - 8 bytes
+    Signature - 64 bytes
+    Payload size - 2 bytes
+    Payload - up to MAX_PAYLOAD_SIZE bytes
+    Amount - biguint
+    Fee - biguint
+
+    Payload is an arbitrary data anchored on chain, like a document hash
+    or a short note. Every byte of it costs FEE_PER_PAYLOAD_BYTE on top
+    of the usual fee, blocks are indexed by hashes of the payloads.
+*/
+
+pub static MAX_PAYLOAD_SIZE: usize = 512;
+pub static FEE_PER_PAYLOAD_BYTE: u64 = 10;
+
+/// Lowest fee of the transaction carrying payload of the given size
+pub fn min_payload_fee(payload_size: usize) -> BigUint {
+    BigUint::from(FEE_PER_PAYLOAD_BYTE) * payload_size
+}
+
+/// Whether fee of the transaction covers its payload
+pub fn check_payload_fee(transaction: &dyn Transactionable) -> bool {
+    let payload = match transaction.get_payload() {
+        Some(payload) => payload,
+        None => return true,
+    };
+
+    match transaction.get_fee() {
+        Some(fee) => *fee >= min_payload_fee(payload.len()),
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoTransaction {
+    sender: [u8; 33],
+    receiver: [u8; 33],
+    timestamp: u64,
+    nonce: u64,
+    signature: [u8; 64],
+    payload: Vec<u8>,
+    amount: BigUint,
+    fee: BigUint,
+}
+
+impl MemoTransaction {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        sender: &[u8; 33],
+        receiver: &[u8; 33],
+        timestamp: u64,
+        signature: &[u8; 64],
+        payload: Vec<u8>,
+        amount: BigUint,
+        fee: BigUint,
+        nonce: u64,
+    ) -> Result<MemoTransaction, TransactionError> {
+        if payload.len() > MAX_PAYLOAD_SIZE {
+            return Err(
+                Report::new(TransactionError::Tx(TxErrorKind::Payload)).attach_printable(format!(
+                    "payload of {} bytes exceeds {} bytes",
+                    payload.len(),
+                    MAX_PAYLOAD_SIZE
+                )),
+            );
+        }
+
+        Ok(MemoTransaction {
+            sender: *sender,
+            receiver: *receiver,
+            timestamp,
+            nonce,
+            signature: *signature,
+            payload,
+            amount,
+            fee,
+        })
+    }
+
+    /// Hash the payload is indexed by
+    pub fn get_payload_hash(&self) -> [u8; 32] {
+        tools::hash(&self.payload)
+    }
+
+    /// data covered by the signature
+    fn signed_data(&self) -> Vec<u8> {
+        let amount_as_bytes = self.amount.to_bytes_be();
+        let fee_as_bytes = self.fee.to_bytes_be();
+
+        let mut concatenated_input: Vec<u8> = Vec::with_capacity(
+            1 + 33
+                + 33
+                + 8
+                + 8
+                + 2
+                + self.payload.len()
+                + 1
+                + amount_as_bytes.len()
+                + fee_as_bytes.len(),
+        );
+        concatenated_input.push(Headers::MemoTransaction as u8);
+        concatenated_input.extend(self.sender.iter());
+        concatenated_input.extend(self.receiver.iter());
+        concatenated_input.extend(self.timestamp.to_be_bytes().iter());
+        concatenated_input.extend(self.nonce.to_be_bytes().iter());
+        concatenated_input.extend((self.payload.len() as u16).to_be_bytes().iter());
+        concatenated_input.extend(self.payload.iter());
+        concatenated_input.push(amount_as_bytes.len() as u8);
+        concatenated_input.extend(amount_as_bytes.iter());
+        concatenated_input.extend(fee_as_bytes.iter());
+
+        concatenated_input
+    }
+}
+
+impl Transactionable for MemoTransaction {
+    fn hash(&self, prev_hash: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+
+        hasher.update(prev_hash);
+        hasher.update(self.signed_data());
+        hasher.update(self.signature);
+
+        hasher.finalize().into()
+    }
+
+    fn hash_without_signature(&self, _prev_hash: &[u8; 32]) -> Box<[u8; 32]> {
+        Box::new(tools::hash(&self.signed_data()))
+    }
+
+    fn get_id(&self) -> [u8; 32] {
+        tools::hash(&self.signed_data())
+    }
+
+    fn verify_with_context(
+        &self,
+        prev_hash: &[u8; 32],
+        verifier: &Secp256k1<VerifyOnly>,
+    ) -> Result<bool, TransactionError> {
+        let message = Message::from_slice(self.hash_without_signature(prev_hash).as_ref())
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let sender = PublicKey::from_slice(&self.sender)
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let signature = Signature::from_compact(&self.signature)
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        Ok(verifier.verify_ecdsa(&message, &signature, &sender).is_ok())
+    }
+
+    fn dump(&self) -> Result<Vec<u8>, TransactionError> {
+        let mut transaction_dump: Vec<u8> = Vec::with_capacity(self.get_dump_size());
+
+        // header
+        transaction_dump.push(Headers::MemoTransaction as u8);
+
+        transaction_dump.extend(self.sender.iter());
+        transaction_dump.extend(self.receiver.iter());
+        transaction_dump.extend(self.timestamp.to_be_bytes().iter());
+        transaction_dump.extend(self.nonce.to_be_bytes().iter());
+        transaction_dump.extend(self.signature.iter());
+
+        // payload
+        transaction_dump.extend((self.payload.len() as u16).to_be_bytes().iter());
+        transaction_dump.extend(self.payload.iter());
+
+        // amount
+        tools::dump_biguint(&self.amount, &mut transaction_dump)
+            .change_context(TransactionError::Tx(TxErrorKind::Dump))?;
+
+        // fee
+        tools::dump_biguint(&self.fee, &mut transaction_dump)
+            .change_context(TransactionError::Tx(TxErrorKind::Dump))?;
+
+        Ok(transaction_dump)
+    }
+
+    fn get_dump_size(&self) -> usize {
+        1 + 33
+            + 33
+            + 8
+            + 8
+            + 64
+            + 2
+            + self.payload.len()
+            + tools::bigint_size(&self.amount)
+            + tools::bigint_size(&self.fee)
+    }
+
+    fn parse(data: &[u8], size: u64) -> Result<MemoTransaction, TransactionError> {
+        if data.len() < 33 + 33 + 8 + 8 + 64 + 2 {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Data length < 148"));
+        }
+        let mut index: usize = 0;
+
+        // parsing sender
+        let sender: [u8; 33] = data[index..index + 33].try_into().unwrap();
+        index += 33;
+
+        // parsing receiver
+        let receiver: [u8; 33] = data[index..index + 33].try_into().unwrap();
+        index += 33;
+
+        // parsing timestamp
+        let timestamp = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
+        index += 8;
+
+        // parsing nonce
+        let nonce = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
+        index += 8;
+
+        // parsing signature
+        let signature: [u8; 64] = data[index..index + 64].try_into().unwrap();
+        index += 64;
+
+        // parsing payload
+        let payload_size = u16::from_be_bytes(data[index..index + 2].try_into().unwrap()) as usize;
+        index += 2;
+
+        if payload_size > MAX_PAYLOAD_SIZE || data.len() < index + payload_size {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse payload"));
+        }
+        let payload = data[index..index + payload_size].to_vec();
+        index += payload_size;
+
+        // parsing amount
+        if data.len() <= index {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse amount"));
+        }
+        let (amount, idx) = tools::load_biguint(&data[index..])
+            .attach_printable("Couldn't parse amount")
+            .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+        index += idx;
+
+        // parsing fee
+        if data.len() <= index {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse fee"));
+        }
+        let (fee, idx) = tools::load_biguint(&data[index..])
+            .attach_printable("Couldn't parse fee")
+            .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+        index += idx;
+
+        if index != size as usize {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Index != Tx size"));
+        }
+
+        Ok(MemoTransaction {
+            sender,
+            receiver,
+            timestamp,
+            nonce,
+            signature,
+            payload,
+            amount,
+            fee,
+        })
+    }
+
+    fn get_sender(&self) -> &[u8; 33] {
+        &self.sender
+    }
+
+    fn get_receiver(&self) -> &[u8; 33] {
+        &self.receiver
+    }
+
+    fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    fn get_signature(&self) -> &[u8; 64] {
+        &self.signature
+    }
+
+    fn get_amount(&self) -> Option<&BigUint> {
+        Some(&self.amount)
+    }
+
+    fn get_nonce(&self) -> Option<u64> {
+        Some(self.nonce)
+    }
+
+    fn get_fee(&self) -> Option<&BigUint> {
+        Some(&self.fee)
+    }
+
+    fn get_lock_time(&self) -> Option<u64> {
+        None
+    }
+
+    fn get_payload(&self) -> Option<&[u8]> {
+        Some(&self.payload)
+    }
+
+    fn get_expiry_height(&self) -> Option<u64> {
+        None
+    }
+
+    fn sign(
+        &mut self,
+        prev_hash: &[u8; 32],
+        private_key: &[u8; 32],
+    ) -> Result<(), TransactionError> {
+        let message = Message::from_slice(self.hash_without_signature(prev_hash).as_ref())
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let secret_key = SecretKey::from_slice(private_key)
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let signer = Secp256k1::new();
+
+        self.signature = signer.sign_ecdsa(&message, &secret_key).serialize_compact();
+
+        Ok(())
+    }
+    fn clone_box(&self) -> Box<dyn Transactionable> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,573 @@
+use crate::errors::*;
+use crate::transaction::{is_expired, Transactionable};
+use num_bigint::BigUint;
+use num_traits::Zero;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+
+use error_stack::{Report, Result};
+
+pub static DEFAULT_MAX_COUNT: usize = 50000;
+pub static DEFAULT_MAX_BYTES: usize = 32 * 1024 * 1024;
+/// 3 days in seconds
+pub static DEFAULT_MAX_AGE: u64 = 60 * 60 * 24 * 3;
+/// Times the pending transaction with the same sender and nonce can be replaced
+pub static MAX_REPLACEMENTS: usize = 10;
+/// Percent the fee of the replacement has to exceed the replaced fee by
+pub static MIN_REPLACEMENT_FEE_BUMP: u64 = 10;
+
+#[derive(Debug)]
+struct PoolEntry {
+    transaction: Box<dyn Transactionable>,
+    size: usize,
+    fee_rate: BigUint,
+    added_at: u64,
+    sequence: u64,
+}
+
+/// priority of the entry, larger is better
+///
+/// higher fee rate goes first, older transactions win ties
+type Priority = (BigUint, Reverse<u64>, [u8; 32]);
+
+#[derive(Debug)]
+pub struct Mempool {
+    entries: HashMap<[u8; 32], PoolEntry>,
+    by_sender: HashMap<[u8; 33], HashSet<[u8; 32]>>,
+    by_receiver: HashMap<[u8; 33], HashSet<[u8; 32]>>,
+    by_nonce: HashMap<([u8; 33], u64), [u8; 32]>,
+    by_priority: BTreeSet<Priority>,
+    /// amount of replacements of the pending sender's nonce
+    replacements: HashMap<([u8; 33], u64), usize>,
+    /// coins reserved by pending transactions of the sender
+    pending_spent: HashMap<[u8; 33], BigUint>,
+    /// transactions evicted to make room, see `take_evicted`
+    evicted: Vec<[u8; 32]>,
+
+    max_count: usize,
+    max_bytes: usize,
+    max_age: u64,
+
+    size_in_bytes: usize,
+    sequence: u64,
+}
+
+/// Fee paid per 1000 bytes of the transaction dump
+pub fn fee_rate(transaction: &dyn Transactionable) -> BigUint {
+    let fee = match transaction.get_fee() {
+        Some(fee) => fee.clone(),
+        None => BigUint::zero(),
+    };
+    fee * 1000u32 / transaction.get_dump_size().max(1)
+}
+
+/// Lowest fee the replacement of the transaction with the given fee has to pay
+pub fn min_replacement_fee(fee: &BigUint) -> BigUint {
+    let bump = fee * MIN_REPLACEMENT_FEE_BUMP / 100u64;
+    fee + bump.max(BigUint::from(1u64))
+}
+
+/// Coins the transaction takes from the sender: amount and fee
+pub fn spent_amount(transaction: &dyn Transactionable) -> BigUint {
+    let mut spent = BigUint::zero();
+    if let Some(amount) = transaction.get_amount() {
+        spent += amount;
+    }
+    if let Some(fee) = transaction.get_fee() {
+        spent += fee;
+    }
+    spent
+}
+
+impl Default for Mempool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_COUNT, DEFAULT_MAX_BYTES, DEFAULT_MAX_AGE)
+    }
+}
+
+impl Mempool {
+    pub fn new(max_count: usize, max_bytes: usize, max_age: u64) -> Mempool {
+        Mempool {
+            entries: HashMap::new(),
+            by_sender: HashMap::new(),
+            by_receiver: HashMap::new(),
+            by_nonce: HashMap::new(),
+            by_priority: BTreeSet::new(),
+            replacements: HashMap::new(),
+            pending_spent: HashMap::new(),
+            evicted: Vec::new(),
+            max_count,
+            max_bytes,
+            max_age,
+            size_in_bytes: 0,
+            sequence: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Summary dump size of all the transactions in the pool
+    pub fn get_size_in_bytes(&self) -> usize {
+        self.size_in_bytes
+    }
+
+    pub fn contains(&self, hash: &[u8; 32]) -> bool {
+        self.entries.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &[u8; 32]) -> Option<&dyn Transactionable> {
+        self.entries
+            .get(hash)
+            .map(|entry| entry.transaction.as_ref())
+    }
+
+    /// Hash of the pending transaction of the sender with the given nonce
+    pub fn find_by_nonce(&self, sender: &[u8; 33], nonce: u64) -> Option<&[u8; 32]> {
+        self.by_nonce.get(&(*sender, nonce))
+    }
+
+    /// Coins reserved by pending transactions of the sender
+    pub fn get_pending_spent(&self, sender: &[u8; 33]) -> BigUint {
+        match self.pending_spent.get(sender) {
+            Some(spent) => spent.clone(),
+            None => BigUint::zero(),
+        }
+    }
+
+    pub fn get_by_sender(&self, addr: &[u8; 33]) -> Vec<&dyn Transactionable> {
+        self.collect(self.by_sender.get(addr))
+    }
+
+    pub fn get_by_receiver(&self, addr: &[u8; 33]) -> Vec<&dyn Transactionable> {
+        self.collect(self.by_receiver.get(addr))
+    }
+
+    fn collect(&self, hashes: Option<&HashSet<[u8; 32]>>) -> Vec<&dyn Transactionable> {
+        match hashes {
+            None => Vec::new(),
+            Some(hashes) => hashes.iter().filter_map(|hash| self.get(hash)).collect(),
+        }
+    }
+
+    /// Transactions ordered from the highest priority to the lowest
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Transactionable> {
+        self.by_priority
+            .iter()
+            .rev()
+            .map(move |(_, _, hash)| self.entries[hash].transaction.as_ref())
+    }
+
+    /// Fee rates of the transactions from the highest to the lowest
+    pub fn get_fee_rates(&self) -> Vec<BigUint> {
+        self.by_priority
+            .iter()
+            .rev()
+            .map(|(fee_rate, _, _)| fee_rate.clone())
+            .collect()
+    }
+
+    /// Transactions together with the time they were added, in the order of addition
+    pub fn iter_with_time(&self) -> impl Iterator<Item = (&dyn Transactionable, u64)> {
+        let mut entries: Vec<&PoolEntry> = self.entries.values().collect();
+        entries.sort_by_key(|entry| entry.sequence);
+        entries
+            .into_iter()
+            .map(|entry| (entry.transaction.as_ref(), entry.added_at))
+    }
+
+    /// Adds transaction to the pool
+    ///
+    /// rejects duplicates and transactions reusing a pending nonce of the sender,
+    /// those have to go through `replace`,
+    /// evicts transactions with the lowest fee rate when the pool is full
+    pub fn add(
+        &mut self,
+        transaction: Box<dyn Transactionable>,
+        now: u64,
+    ) -> Result<[u8; 32], MempoolError> {
+        let hash = transaction.get_id();
+
+        if self.entries.contains_key(&hash) {
+            return Err(Report::new(MempoolError::Mempool(
+                MempoolErrorKind::Duplicate,
+            )));
+        }
+
+        if let Some(nonce) = transaction.get_nonce() {
+            if self
+                .by_nonce
+                .contains_key(&(*transaction.get_sender(), nonce))
+            {
+                return Err(
+                    Report::new(MempoolError::Mempool(MempoolErrorKind::Conflict))
+                        .attach_printable(format!("nonce {} is already pending", nonce)),
+                );
+            }
+        }
+
+        let size = transaction.get_dump_size();
+        let fee_rate = fee_rate(transaction.as_ref());
+
+        if size > self.max_bytes {
+            return Err(
+                Report::new(MempoolError::Mempool(MempoolErrorKind::PoolIsFull))
+                    .attach_printable("transaction is larger than the pool"),
+            );
+        }
+
+        // make room for the new transaction
+        while self.entries.len() + 1 > self.max_count || self.size_in_bytes + size > self.max_bytes
+        {
+            let lowest_hash = match self.find_evictable(transaction.as_ref()) {
+                Some(lowest) if lowest.0 < fee_rate => lowest.2,
+                _ => {
+                    return Err(
+                        Report::new(MempoolError::Mempool(MempoolErrorKind::PoolIsFull))
+                            .attach_printable("fee rate is too low"),
+                    );
+                }
+            };
+            self.remove(&lowest_hash);
+            self.evicted.push(lowest_hash);
+        }
+
+        self.insert_entry(hash, transaction, size, fee_rate, now);
+
+        Ok(hash)
+    }
+
+    /// Replaces pending transaction of the sender with the same nonce
+    ///
+    /// replacement has to pay at least `min_replacement_fee` of the replaced one
+    /// without lowering its fee rate, the same nonce can be replaced
+    /// at most MAX_REPLACEMENTS times while it stays in the pool.
+    /// Returns hash of the replacement and the replaced transaction
+    pub fn replace(
+        &mut self,
+        transaction: Box<dyn Transactionable>,
+        now: u64,
+    ) -> Result<([u8; 32], Box<dyn Transactionable>), MempoolError> {
+        let sender = *transaction.get_sender();
+        let nonce = match transaction.get_nonce() {
+            Some(nonce) => nonce,
+            None => {
+                return Err(
+                    Report::new(MempoolError::Mempool(MempoolErrorKind::Replacement))
+                        .attach_printable("transaction has no nonce"),
+                );
+            }
+        };
+
+        let replaced_hash = match self.by_nonce.get(&(sender, nonce)) {
+            Some(hash) => *hash,
+            None => {
+                return Err(
+                    Report::new(MempoolError::Mempool(MempoolErrorKind::Replacement))
+                        .attach_printable(format!("nonce {} is not pending", nonce)),
+                );
+            }
+        };
+        let replaced = &self.entries[&replaced_hash];
+
+        let replacements = self
+            .replacements
+            .get(&(sender, nonce))
+            .copied()
+            .unwrap_or(0);
+        if replacements >= MAX_REPLACEMENTS {
+            return Err(
+                Report::new(MempoolError::Mempool(MempoolErrorKind::Replacement))
+                    .attach_printable(format!("nonce {} was replaced too many times", nonce)),
+            );
+        }
+
+        let old_fee = match replaced.transaction.get_fee() {
+            Some(fee) => fee.clone(),
+            None => BigUint::zero(),
+        };
+        let min_fee = min_replacement_fee(&old_fee);
+        if transaction.get_fee().is_none_or(|fee| *fee < min_fee) {
+            return Err(
+                Report::new(MempoolError::Mempool(MempoolErrorKind::Replacement))
+                    .attach_printable(format!("fee has to be at least {}", min_fee)),
+            );
+        }
+
+        if fee_rate(transaction.as_ref()) < replaced.fee_rate {
+            return Err(
+                Report::new(MempoolError::Mempool(MempoolErrorKind::Replacement))
+                    .attach_printable("fee rate is lower than of the replaced transaction"),
+            );
+        }
+
+        let added_at = replaced.added_at;
+        let replaced = self.remove(&replaced_hash).unwrap();
+
+        match self.add(transaction, now) {
+            Ok(hash) => {
+                self.replacements.insert((sender, nonce), replacements + 1);
+                Ok((hash, replaced))
+            }
+            Err(report) => {
+                // pool is full, keep the replaced transaction
+                let size = replaced.get_dump_size();
+                let fee_rate = fee_rate(replaced.as_ref());
+                self.insert_entry(replaced_hash, replaced, size, fee_rate, added_at);
+                if replacements > 0 {
+                    self.replacements.insert((sender, nonce), replacements);
+                }
+                Err(report)
+            }
+        }
+    }
+
+    /// Lowest priority transaction that can be evicted without leaving a gap
+    /// in pending nonces of its sender or of the new transaction's sender
+    fn find_evictable(&self, new: &dyn Transactionable) -> Option<&Priority> {
+        self.by_priority.iter().find(|(_, _, hash)| {
+            let transaction = &self.entries[hash].transaction;
+            let nonce = match transaction.get_nonce() {
+                Some(nonce) => nonce,
+                None => return true,
+            };
+
+            let has_successor = self
+                .by_nonce
+                .contains_key(&(*transaction.get_sender(), nonce + 1));
+            let precedes_new = transaction.get_sender() == new.get_sender()
+                && matches!(new.get_nonce(), Some(new_nonce) if nonce < new_nonce);
+
+            !has_successor && !precedes_new
+        })
+    }
+
+    fn insert_entry(
+        &mut self,
+        hash: [u8; 32],
+        transaction: Box<dyn Transactionable>,
+        size: usize,
+        fee_rate: BigUint,
+        now: u64,
+    ) {
+        self.sequence += 1;
+
+        self.by_sender
+            .entry(*transaction.get_sender())
+            .or_default()
+            .insert(hash);
+        self.by_receiver
+            .entry(*transaction.get_receiver())
+            .or_default()
+            .insert(hash);
+        if let Some(nonce) = transaction.get_nonce() {
+            self.by_nonce
+                .insert((*transaction.get_sender(), nonce), hash);
+        }
+        *self
+            .pending_spent
+            .entry(*transaction.get_sender())
+            .or_default() += spent_amount(transaction.as_ref());
+        self.by_priority
+            .insert((fee_rate.clone(), Reverse(self.sequence), hash));
+        self.size_in_bytes += size;
+
+        self.entries.insert(
+            hash,
+            PoolEntry {
+                transaction,
+                size,
+                fee_rate,
+                added_at: now,
+                sequence: self.sequence,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, hash: &[u8; 32]) -> Option<Box<dyn Transactionable>> {
+        let entry = self.entries.remove(hash)?;
+
+        let sender = entry.transaction.get_sender();
+        if let Some(hashes) = self.by_sender.get_mut(sender) {
+            hashes.remove(hash);
+            if hashes.is_empty() {
+                self.by_sender.remove(sender);
+            }
+        }
+
+        let receiver = entry.transaction.get_receiver();
+        if let Some(hashes) = self.by_receiver.get_mut(receiver) {
+            hashes.remove(hash);
+            if hashes.is_empty() {
+                self.by_receiver.remove(receiver);
+            }
+        }
+
+        if let Some(nonce) = entry.transaction.get_nonce() {
+            self.by_nonce.remove(&(*sender, nonce));
+            self.replacements.remove(&(*sender, nonce));
+        }
+
+        if let Some(spent) = self.pending_spent.get_mut(sender) {
+            *spent -= spent_amount(entry.transaction.as_ref());
+            if spent.is_zero() {
+                self.pending_spent.remove(sender);
+            }
+        }
+
+        self.by_priority
+            .remove(&(entry.fee_rate, Reverse(entry.sequence), *hash));
+        self.size_in_bytes -= entry.size;
+
+        Some(entry.transaction)
+    }
+
+    /// Removes transaction together with pending transactions of the same sender
+    /// with higher nonces, as they can't be included without it
+    pub fn remove_with_successors(&mut self, hash: &[u8; 32]) -> Vec<Box<dyn Transactionable>> {
+        let mut removed: Vec<Box<dyn Transactionable>> = Vec::new();
+
+        let transaction = match self.remove(hash) {
+            Some(transaction) => transaction,
+            None => return removed,
+        };
+
+        if let Some(mut nonce) = transaction.get_nonce() {
+            let sender = *transaction.get_sender();
+            while let Some(next) = self.by_nonce.get(&(sender, nonce + 1)).copied() {
+                removed.extend(self.remove(&next));
+                nonce += 1;
+            }
+        }
+        removed.insert(0, transaction);
+
+        removed
+    }
+
+    /// Drops pending transactions of the sender with nonces below the committed one
+    /// and their successors
+    pub fn remove_below_nonce(
+        &mut self,
+        sender: &[u8; 33],
+        committed_nonce: u64,
+    ) -> Vec<Box<dyn Transactionable>> {
+        let stale: Vec<[u8; 32]> = match self.by_sender.get(sender) {
+            Some(hashes) => hashes
+                .iter()
+                .filter(|hash| {
+                    self.entries[*hash]
+                        .transaction
+                        .get_nonce()
+                        .is_some_and(|nonce| nonce < committed_nonce)
+                })
+                .copied()
+                .collect(),
+            None => return Vec::new(),
+        };
+
+        let mut removed: Vec<Box<dyn Transactionable>> = Vec::new();
+        for hash in stale.iter() {
+            removed.extend(self.remove_with_successors(hash));
+        }
+
+        removed
+    }
+
+    /// Ids of transactions evicted by `add` since the last call
+    pub fn take_evicted(&mut self) -> Vec<[u8; 32]> {
+        std::mem::take(&mut self.evicted)
+    }
+
+    /// Drops transactions that stayed in the pool longer than max age
+    /// and their successors
+    pub fn remove_expired(&mut self, now: u64) -> Vec<Box<dyn Transactionable>> {
+        let expired: Vec<[u8; 32]> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| now.saturating_sub(entry.added_at) > self.max_age)
+            .map(|(hash, _)| *hash)
+            .collect();
+
+        let mut removed: Vec<Box<dyn Transactionable>> = Vec::new();
+        for hash in expired.iter() {
+            removed.extend(self.remove_with_successors(hash));
+        }
+
+        removed
+    }
+
+    /// Drops transactions that can't be included in the block with the given height
+    /// and their successors
+    pub fn remove_expired_at_height(&mut self, height: u64) -> Vec<Box<dyn Transactionable>> {
+        let expired: Vec<[u8; 32]> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| {
+                entry
+                    .transaction
+                    .get_expiry_height()
+                    .is_some_and(|expiry_height| is_expired(expiry_height, height))
+            })
+            .map(|(hash, _)| *hash)
+            .collect();
+
+        let mut removed: Vec<Box<dyn Transactionable>> = Vec::new();
+        for hash in expired.iter() {
+            removed.extend(self.remove_with_successors(hash));
+        }
+
+        removed
+    }
+
+    /// Takes up to `limit` transactions with the highest fee rate out of the pool
+    ///
+    /// transactions of the same sender are returned in the order of their nonces
+    pub fn pop_best(&mut self, limit: usize) -> Vec<Box<dyn Transactionable>> {
+        // lowest pending nonce of every sender
+        let mut first_nonces: HashMap<[u8; 33], u64> = HashMap::new();
+        for (sender, nonce) in self.by_nonce.keys() {
+            let first = first_nonces.entry(*sender).or_insert(*nonce);
+            if *nonce < *first {
+                *first = *nonce;
+            }
+        }
+
+        let mut ready: BinaryHeap<Priority> = BinaryHeap::new();
+        for (hash, entry) in self.entries.iter() {
+            let is_ready = match entry.transaction.get_nonce() {
+                None => true,
+                Some(nonce) => first_nonces[entry.transaction.get_sender()] == nonce,
+            };
+            if is_ready {
+                ready.push((entry.fee_rate.clone(), Reverse(entry.sequence), *hash));
+            }
+        }
+
+        let mut to_return: Vec<Box<dyn Transactionable>> = Vec::with_capacity(limit);
+        while to_return.len() < limit {
+            let (_, _, hash) = match ready.pop() {
+                Some(priority) => priority,
+                None => break,
+            };
+
+            let transaction = self.remove(&hash).unwrap();
+
+            // next transaction of the sender becomes ready
+            if let Some(nonce) = transaction.get_nonce() {
+                if let Some(next) = self.by_nonce.get(&(*transaction.get_sender(), nonce + 1)) {
+                    let entry = &self.entries[next];
+                    ready.push((entry.fee_rate.clone(), Reverse(entry.sequence), *next));
+                }
+            }
+
+            to_return.push(transaction);
+        }
+
+        to_return
+    }
+}
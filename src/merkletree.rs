@@ -1,6 +1,6 @@
 use error_stack::{Report, Result};
+use hex::ToHex;
 use sha2::{Digest, Sha256};
-use std::convert::TryInto;
 
 use crate::errors::*;
 
@@ -26,6 +26,14 @@ pub fn find_closest_power_of_2(number: usize) -> usize {
     power
 }
 
+/// Hash of the parent node, children are hashed in order
+pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
 impl Default for MerkleTree {
     fn default() -> Self {
         Self::new()
@@ -50,23 +58,11 @@ impl MerkleTree {
         if right_child >= self.array_representation.len() {
             return None;
         }
-        let mut hasher = Sha256::new();
 
-        let mut hash_input: [u8; 32];
-        if self.array_representation[left_child].is_some() {
-            hash_input = self.array_representation[left_child].unwrap();
-        } else {
-            hash_input = PADDING_HASH;
-        }
-        if self.array_representation[right_child].is_some() {
-            for (idx, hinput) in hash_input.iter_mut().enumerate() {
-                *hinput &= self.array_representation[right_child].unwrap()[idx];
-            }
-        }
+        let left = self.array_representation[left_child].unwrap_or(PADDING_HASH);
+        let right = self.array_representation[right_child].unwrap_or(PADDING_HASH);
 
-        hasher.update(hash_input);
-        let result: [u8; 32] = hasher.finalize().as_slice().try_into().unwrap();
-        Some(result)
+        Some(hash_pair(&left, &right))
     }
 
     fn populate_tree(&mut self, right_node: usize, right_branch: bool) {
@@ -93,7 +89,8 @@ impl MerkleTree {
 
         let initial_length = input.len();
         self.depth = find_closest_power_of_2(initial_length);
-        if initial_length % 2 != 0 {
+        // a single input is padded too, so every leaf has a sibling
+        if initial_length < 2 || !initial_length.is_power_of_two() {
             for _ in initial_length..usize::pow(2, self.depth as u32) {
                 input.push(&PADDING_HASH);
             }
@@ -129,9 +126,9 @@ impl MerkleTree {
     }
 
     fn exists(&self, hash: &[u8; 32]) -> Option<usize> {
-        for i in self.array_representation.len() - self.initial_amount_of_inputs - 1
-            ..self.array_representation.len()
-        {
+        // leaves are the last half of the tree, padding goes after the inputs
+        let first_leaf = self.array_representation.len() / 2;
+        for i in first_leaf..first_leaf + self.initial_amount_of_inputs {
             self.array_representation[i]?;
 
             let mut equal: bool = true;
@@ -159,9 +156,8 @@ impl MerkleTree {
                 MerkleTreeErrorKind::GettingProof,
             ))
             .attach_printable(format!(
-                "hash: {:?} // {} doesn't exist",
-                hash,
-                std::str::from_utf8(hash).unwrap()
+                "hash {} doesn't exist",
+                hash.encode_hex::<String>()
             )));
         }
 
@@ -169,7 +165,7 @@ impl MerkleTree {
 
         let mut to_return: Vec<&'a [u8; 32]> = Vec::with_capacity(self.depth);
         while starting_node != 0 {
-            if starting_node % 2 == 0 {
+            if starting_node.is_multiple_of(2) {
                 match self.array_representation[starting_node - 1] {
                     Some(ref data) => {
                         to_return.push(data);
@@ -194,34 +190,29 @@ impl MerkleTree {
         Ok(to_return)
     }
     pub fn get_root(&self) -> &[u8; 32] {
-        return self.array_representation[0].as_ref().unwrap();
+        self.array_representation[0].as_ref().unwrap()
     }
 }
 
-pub fn verify_proof(hash: &[u8; 32], root: &[u8; 32], proof: Vec<&[u8; 32]>) -> bool {
-    let mut hasher = Sha256::new();
-    let mut calculated_root: [u8; 32] = [0; 32];
-
-    for (n, i) in calculated_root.iter_mut().enumerate() {
-        *i = hash[n] & proof[0][n];
-    }
-    hasher.update(calculated_root);
-    calculated_root = hasher.finalize().as_slice().try_into().unwrap();
-
-    for idx in proof.iter().skip(1) {
-        let mut hasher = Sha256::new();
-
-        for (n, item) in calculated_root.iter_mut().enumerate() {
-            *item &= idx[n]
-        }
-        hasher.update(calculated_root);
-        calculated_root = hasher.finalize().as_slice().try_into().unwrap();
+/// Checks the proof of the hash at the given leaf position
+///
+/// position decides on which side the sibling is hashed at each level
+pub fn verify_proof(
+    hash: &[u8; 32],
+    root: &[u8; 32],
+    proof: Vec<&[u8; 32]>,
+    mut position: usize,
+) -> bool {
+    let mut calculated_root: [u8; 32] = *hash;
+
+    for sibling in proof.iter() {
+        calculated_root = if position.is_multiple_of(2) {
+            hash_pair(&calculated_root, sibling)
+        } else {
+            hash_pair(sibling, &calculated_root)
+        };
+        position /= 2;
     }
 
-    for i in 0..32 {
-        if root[i] != calculated_root[i] {
-            return false;
-        }
-    }
-    true
+    root == &calculated_root
 }
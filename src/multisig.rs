@@ -0,0 +1,716 @@
+use crate::dump_headers::Headers;
+use crate::errors::*;
+use crate::tools;
+use crate::transaction::Transactionable;
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::convert::TryInto;
+
+use secp256k1::ecdsa::Signature;
+use secp256k1::PublicKey;
+use secp256k1::{Message, Secp256k1, SecretKey, VerifyOnly};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+/*
+    Multisig registration dumping protocol
+
+    Header (Headers::MultisigRegistration) - 1 byte
+    Registrar - 33 bytes
+    Timestamp - 8 bytes
+    Nonce - 8 bytes
+    Threshold - 1 byte
+    Amount of keys - 1 byte
+    Keys - 33 bytes each
+    Fee - biguint
+    Signature - 64 bytes
+
+    Registration stores the key set on chain under the address of the account,
+    the registrar pays the fee with the nonce like transfers do.
+    Address of the account is a commitment to its threshold and keys,
+    so only the right key set can be registered under it, once.
+
+    Multisig transaction dumping protocol
+
+    Header (Headers::MultisigTransaction) - 1 byte
+    Sender - 33 bytes, address of the registered multisig account
+    Receiver - 33 bytes
+    Timestamp - 8 bytes
+    Nonce - 8 bytes
+    Amount of signatures - 1 byte
+    Signatures - key 33 bytes + signature 64 bytes each, ordered by key
+    Amount - biguint
+    Fee - biguint
+
+    Signatures are checked on their own, keys of the signers are then
+    looked up in the key set registered for the sender, see `is_authorized`
+*/
+
+/// first byte of multisig addresses, compressed public keys start with 0x02 or 0x03
+pub static MULTISIG_ADDRESS_PREFIX: u8 = 0x05;
+pub static MAX_KEYS: usize = 16;
+
+static EMPTY_SIGNATURE: [u8; 64] = [0; 64];
+
+/// M-of-N set of keys
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigAccount {
+    threshold: u8,
+    keys: Vec<[u8; 33]>,
+}
+
+impl MultisigAccount {
+    pub fn new(threshold: u8, keys: Vec<[u8; 33]>) -> Result<MultisigAccount, TransactionError> {
+        if threshold == 0 || threshold as usize > keys.len() || keys.len() > MAX_KEYS {
+            return Err(
+                Report::new(TransactionError::Tx(TxErrorKind::Multisig)).attach_printable(format!(
+                    "wrong threshold {} of {} keys",
+                    threshold,
+                    keys.len()
+                )),
+            );
+        }
+
+        for (i, key) in keys.iter().enumerate() {
+            if keys[..i].contains(key) {
+                return Err(Report::new(TransactionError::Tx(TxErrorKind::Multisig))
+                    .attach_printable("duplicated key"));
+            }
+        }
+
+        Ok(MultisigAccount { threshold, keys })
+    }
+
+    pub fn get_threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn get_keys(&self) -> &[[u8; 33]] {
+        &self.keys
+    }
+
+    pub fn dump(&self, buffer: &mut Vec<u8>) {
+        buffer.push(self.threshold);
+        buffer.push(self.keys.len() as u8);
+        for key in self.keys.iter() {
+            buffer.extend(key.iter());
+        }
+    }
+
+    pub fn get_dump_size(&self) -> usize {
+        2 + 33 * self.keys.len()
+    }
+
+    /// Parses the account starting at the index, moves the index past it
+    pub fn parse(data: &[u8], index: &mut usize) -> Result<MultisigAccount, TransactionError> {
+        if data.len() < *index + 2 {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse keys"));
+        }
+        let threshold = data[*index];
+        let amount_of_keys = data[*index + 1] as usize;
+        *index += 2;
+
+        if data.len() < *index + 33 * amount_of_keys {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse keys"));
+        }
+
+        let mut keys: Vec<[u8; 33]> = Vec::with_capacity(amount_of_keys);
+        for _ in 0..amount_of_keys {
+            keys.push(data[*index..*index + 33].try_into().unwrap());
+            *index += 33;
+        }
+
+        MultisigAccount::new(threshold, keys)
+            .change_context(TransactionError::Tx(TxErrorKind::Parse))
+    }
+
+    /// Address funds of the account are kept at
+    pub fn get_address(&self) -> [u8; 33] {
+        let mut dump: Vec<u8> = Vec::with_capacity(self.get_dump_size());
+        self.dump(&mut dump);
+
+        let mut address = [MULTISIG_ADDRESS_PREFIX; 33];
+        address[1..].copy_from_slice(&tools::hash(&dump));
+        address
+    }
+}
+
+/// size of the registration without the key set and the fee
+static REGISTRATION_SIZE: usize = 1 + 33 + 8 + 8 + 64;
+
+/// Transaction storing the key set of the multisig account on chain
+#[derive(Debug, Clone)]
+pub struct MultisigRegistration {
+    registrar: [u8; 33],
+    address: [u8; 33],
+    timestamp: u64,
+    nonce: u64,
+    account: MultisigAccount,
+    fee: BigUint,
+    signature: [u8; 64],
+}
+
+impl MultisigRegistration {
+    pub fn new(
+        registrar: &[u8; 33],
+        account: MultisigAccount,
+        timestamp: u64,
+        fee: BigUint,
+        nonce: u64,
+    ) -> MultisigRegistration {
+        MultisigRegistration {
+            registrar: *registrar,
+            address: account.get_address(),
+            timestamp,
+            nonce,
+            account,
+            fee,
+            signature: EMPTY_SIGNATURE,
+        }
+    }
+
+    pub fn get_account(&self) -> &MultisigAccount {
+        &self.account
+    }
+
+    /// data covered by the signature
+    fn signed_data(&self) -> Vec<u8> {
+        let mut signed_data: Vec<u8> = Vec::with_capacity(self.get_dump_size() - 64);
+
+        signed_data.push(Headers::MultisigRegistration as u8);
+        signed_data.extend(self.registrar.iter());
+        signed_data.extend(self.timestamp.to_be_bytes().iter());
+        signed_data.extend(self.nonce.to_be_bytes().iter());
+        self.account.dump(&mut signed_data);
+        signed_data.extend(self.fee.to_bytes_be().iter());
+
+        signed_data
+    }
+}
+
+impl Transactionable for MultisigRegistration {
+    fn hash(&self, prev_hash: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+
+        hasher.update(prev_hash);
+        hasher.update(self.signed_data());
+        hasher.update(self.signature);
+
+        hasher.finalize().into()
+    }
+
+    fn hash_without_signature(&self, _prev_hash: &[u8; 32]) -> Box<[u8; 32]> {
+        Box::new(tools::hash(&self.signed_data()))
+    }
+
+    fn get_id(&self) -> [u8; 32] {
+        tools::hash(&self.signed_data())
+    }
+
+    fn verify_with_context(
+        &self,
+        prev_hash: &[u8; 32],
+        verifier: &Secp256k1<VerifyOnly>,
+    ) -> Result<bool, TransactionError> {
+        let message = Message::from_slice(self.hash_without_signature(prev_hash).as_ref())
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let registrar = PublicKey::from_slice(&self.registrar)
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let signature = Signature::from_compact(&self.signature)
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        Ok(verifier
+            .verify_ecdsa(&message, &signature, &registrar)
+            .is_ok())
+    }
+
+    fn dump(&self) -> Result<Vec<u8>, TransactionError> {
+        let mut registration_dump: Vec<u8> = Vec::with_capacity(self.get_dump_size());
+
+        // header
+        registration_dump.push(Headers::MultisigRegistration as u8);
+
+        registration_dump.extend(self.registrar.iter());
+        registration_dump.extend(self.timestamp.to_be_bytes().iter());
+        registration_dump.extend(self.nonce.to_be_bytes().iter());
+
+        // keys
+        self.account.dump(&mut registration_dump);
+
+        // fee
+        tools::dump_biguint(&self.fee, &mut registration_dump)
+            .change_context(TransactionError::Tx(TxErrorKind::Dump))?;
+
+        registration_dump.extend(self.signature.iter());
+
+        Ok(registration_dump)
+    }
+
+    fn get_dump_size(&self) -> usize {
+        REGISTRATION_SIZE + self.account.get_dump_size() + tools::bigint_size(&self.fee)
+    }
+
+    fn parse(data: &[u8], size: u64) -> Result<MultisigRegistration, TransactionError> {
+        if data.len() < 33 + 8 + 8 {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Data length < 49"));
+        }
+        let mut index: usize = 0;
+
+        // parsing registrar
+        let registrar: [u8; 33] = data[index..index + 33].try_into().unwrap();
+        index += 33;
+
+        // parsing timestamp
+        let timestamp = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
+        index += 8;
+
+        // parsing nonce
+        let nonce = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
+        index += 8;
+
+        // parsing keys
+        let account = MultisigAccount::parse(data, &mut index)?;
+
+        // parsing fee
+        if data.len() <= index {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse fee"));
+        }
+        let (fee, idx) = tools::load_biguint(&data[index..])
+            .attach_printable("Couldn't parse fee")
+            .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+        index += idx;
+
+        // parsing signature
+        if data.len() < index + 64 {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse signature"));
+        }
+        let signature: [u8; 64] = data[index..index + 64].try_into().unwrap();
+        index += 64;
+
+        if index != size as usize {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Index != Tx size"));
+        }
+
+        Ok(MultisigRegistration {
+            registrar,
+            address: account.get_address(),
+            timestamp,
+            nonce,
+            account,
+            fee,
+            signature,
+        })
+    }
+
+    fn get_sender(&self) -> &[u8; 33] {
+        &self.registrar
+    }
+
+    /// address of the registered account
+    fn get_receiver(&self) -> &[u8; 33] {
+        &self.address
+    }
+
+    fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    fn get_signature(&self) -> &[u8; 64] {
+        &self.signature
+    }
+
+    fn get_amount(&self) -> Option<&BigUint> {
+        None
+    }
+
+    fn get_nonce(&self) -> Option<u64> {
+        Some(self.nonce)
+    }
+
+    fn get_fee(&self) -> Option<&BigUint> {
+        Some(&self.fee)
+    }
+
+    fn get_lock_time(&self) -> Option<u64> {
+        None
+    }
+
+    fn get_payload(&self) -> Option<&[u8]> {
+        None
+    }
+
+    fn get_expiry_height(&self) -> Option<u64> {
+        None
+    }
+
+    fn get_multisig_registration(&self) -> Option<&MultisigRegistration> {
+        Some(self)
+    }
+
+    fn sign(
+        &mut self,
+        prev_hash: &[u8; 32],
+        private_key: &[u8; 32],
+    ) -> Result<(), TransactionError> {
+        let message = Message::from_slice(self.hash_without_signature(prev_hash).as_ref())
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let secret_key = SecretKey::from_slice(private_key)
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let signer = Secp256k1::new();
+
+        self.signature = signer.sign_ecdsa(&message, &secret_key).serialize_compact();
+
+        Ok(())
+    }
+    fn clone_box(&self) -> Box<dyn Transactionable> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MultisigTransaction {
+    sender: [u8; 33],
+    receiver: [u8; 33],
+    timestamp: u64,
+    nonce: u64,
+    signatures: Vec<([u8; 33], [u8; 64])>,
+    amount: BigUint,
+    fee: BigUint,
+}
+
+impl MultisigTransaction {
+    pub fn new(
+        sender: &[u8; 33],
+        receiver: &[u8; 33],
+        timestamp: u64,
+        amount: BigUint,
+        fee: BigUint,
+        nonce: u64,
+    ) -> MultisigTransaction {
+        MultisigTransaction {
+            sender: *sender,
+            receiver: *receiver,
+            timestamp,
+            nonce,
+            signatures: Vec::new(),
+            amount,
+            fee,
+        }
+    }
+
+    /// Signatures with keys that made them
+    pub fn get_signatures(&self) -> &[([u8; 33], [u8; 64])] {
+        &self.signatures
+    }
+
+    /// Whether the signers are enough to spend from the account
+    ///
+    /// signatures themselves are checked by `verify`
+    pub fn is_authorized(&self, account: &MultisigAccount) -> bool {
+        self.sender == account.get_address()
+            && self.signatures.len() >= account.get_threshold() as usize
+            && self
+                .signatures
+                .iter()
+                .all(|(key, _)| account.get_keys().contains(key))
+    }
+
+    /// data covered by the signatures
+    fn signed_data(&self) -> Vec<u8> {
+        let amount_as_bytes = self.amount.to_bytes_be();
+        let fee_as_bytes = self.fee.to_bytes_be();
+
+        let mut concatenated_input: Vec<u8> = Vec::with_capacity(
+            1 + 33 + 33 + 8 + 8 + 1 + amount_as_bytes.len() + fee_as_bytes.len(),
+        );
+        concatenated_input.push(Headers::MultisigTransaction as u8);
+        concatenated_input.extend(self.sender.iter());
+        concatenated_input.extend(self.receiver.iter());
+        concatenated_input.extend(self.timestamp.to_be_bytes().iter());
+        concatenated_input.extend(self.nonce.to_be_bytes().iter());
+        concatenated_input.push(amount_as_bytes.len() as u8);
+        concatenated_input.extend(amount_as_bytes.iter());
+        concatenated_input.extend(fee_as_bytes.iter());
+
+        concatenated_input
+    }
+}
+
+impl Transactionable for MultisigTransaction {
+    fn hash(&self, prev_hash: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+
+        hasher.update(prev_hash);
+        hasher.update(self.signed_data());
+        for (key, signature) in self.signatures.iter() {
+            hasher.update(key);
+            hasher.update(signature);
+        }
+
+        hasher.finalize().into()
+    }
+
+    fn hash_without_signature(&self, _prev_hash: &[u8; 32]) -> Box<[u8; 32]> {
+        Box::new(tools::hash(&self.signed_data()))
+    }
+
+    fn get_id(&self) -> [u8; 32] {
+        tools::hash(&self.signed_data())
+    }
+
+    fn verify_with_context(
+        &self,
+        prev_hash: &[u8; 32],
+        verifier: &Secp256k1<VerifyOnly>,
+    ) -> Result<bool, TransactionError> {
+        if self.signatures.is_empty() {
+            return Ok(false);
+        }
+
+        let message = Message::from_slice(self.hash_without_signature(prev_hash).as_ref())
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let mut previous_key: Option<&[u8; 33]> = None;
+        for (key, signature) in self.signatures.iter() {
+            // every key signs once
+            if previous_key.is_some_and(|previous| previous >= key) {
+                return Ok(false);
+            }
+            previous_key = Some(key);
+
+            let public_key = PublicKey::from_slice(key)
+                .report()
+                .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+            let signature = Signature::from_compact(signature)
+                .report()
+                .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+            if verifier
+                .verify_ecdsa(&message, &signature, &public_key)
+                .is_err()
+            {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn dump(&self) -> Result<Vec<u8>, TransactionError> {
+        let mut transaction_dump: Vec<u8> = Vec::with_capacity(self.get_dump_size());
+
+        // header
+        transaction_dump.push(Headers::MultisigTransaction as u8);
+
+        transaction_dump.extend(self.sender.iter());
+        transaction_dump.extend(self.receiver.iter());
+        transaction_dump.extend(self.timestamp.to_be_bytes().iter());
+        transaction_dump.extend(self.nonce.to_be_bytes().iter());
+
+        // signatures
+        transaction_dump.push(self.signatures.len() as u8);
+        for (key, signature) in self.signatures.iter() {
+            transaction_dump.extend(key.iter());
+            transaction_dump.extend(signature.iter());
+        }
+
+        // amount
+        tools::dump_biguint(&self.amount, &mut transaction_dump)
+            .change_context(TransactionError::Tx(TxErrorKind::Dump))?;
+
+        // fee
+        tools::dump_biguint(&self.fee, &mut transaction_dump)
+            .change_context(TransactionError::Tx(TxErrorKind::Dump))?;
+
+        Ok(transaction_dump)
+    }
+
+    fn get_dump_size(&self) -> usize {
+        1 + 33
+            + 33
+            + 8
+            + 8
+            + 1
+            + 97 * self.signatures.len()
+            + tools::bigint_size(&self.amount)
+            + tools::bigint_size(&self.fee)
+    }
+
+    fn parse(data: &[u8], size: u64) -> Result<MultisigTransaction, TransactionError> {
+        if data.len() < 33 + 33 + 8 + 8 {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Data length < 82"));
+        }
+        let mut index: usize = 0;
+
+        // parsing sender
+        let sender: [u8; 33] = data[index..index + 33].try_into().unwrap();
+        index += 33;
+
+        // parsing receiver
+        let receiver: [u8; 33] = data[index..index + 33].try_into().unwrap();
+        index += 33;
+
+        // parsing timestamp
+        let timestamp = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
+        index += 8;
+
+        // parsing nonce
+        let nonce = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
+        index += 8;
+
+        // parsing signatures
+        if data.len() <= index {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse signatures"));
+        }
+        let amount_of_signatures = data[index] as usize;
+        index += 1;
+
+        if amount_of_signatures > MAX_KEYS || data.len() < index + 97 * amount_of_signatures {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse signatures"));
+        }
+
+        let mut signatures: Vec<([u8; 33], [u8; 64])> = Vec::with_capacity(amount_of_signatures);
+        for _ in 0..amount_of_signatures {
+            let key: [u8; 33] = data[index..index + 33].try_into().unwrap();
+            let signature: [u8; 64] = data[index + 33..index + 97].try_into().unwrap();
+            signatures.push((key, signature));
+            index += 97;
+        }
+
+        // parsing amount
+        if data.len() <= index {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse amount"));
+        }
+        let (amount, idx) = tools::load_biguint(&data[index..])
+            .attach_printable("Couldn't parse amount")
+            .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+        index += idx;
+
+        // parsing fee
+        if data.len() <= index {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse fee"));
+        }
+        let (fee, idx) = tools::load_biguint(&data[index..])
+            .attach_printable("Couldn't parse fee")
+            .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+        index += idx;
+
+        if index != size as usize {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Index != Tx size"));
+        }
+
+        Ok(MultisigTransaction {
+            sender,
+            receiver,
+            timestamp,
+            nonce,
+            signatures,
+            amount,
+            fee,
+        })
+    }
+
+    fn get_sender(&self) -> &[u8; 33] {
+        &self.sender
+    }
+
+    fn get_receiver(&self) -> &[u8; 33] {
+        &self.receiver
+    }
+
+    fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// signature of the first signer
+    fn get_signature(&self) -> &[u8; 64] {
+        match self.signatures.first() {
+            Some((_, signature)) => signature,
+            None => &EMPTY_SIGNATURE,
+        }
+    }
+
+    fn get_amount(&self) -> Option<&BigUint> {
+        Some(&self.amount)
+    }
+
+    fn get_nonce(&self) -> Option<u64> {
+        Some(self.nonce)
+    }
+
+    fn get_fee(&self) -> Option<&BigUint> {
+        Some(&self.fee)
+    }
+
+    fn get_lock_time(&self) -> Option<u64> {
+        None
+    }
+
+    fn get_payload(&self) -> Option<&[u8]> {
+        None
+    }
+
+    fn get_expiry_height(&self) -> Option<u64> {
+        None
+    }
+
+    fn get_multisig(&self) -> Option<&MultisigTransaction> {
+        Some(self)
+    }
+
+    /// Adds signature of the key, replacing its previous signature
+    fn sign(
+        &mut self,
+        prev_hash: &[u8; 32],
+        private_key: &[u8; 32],
+    ) -> Result<(), TransactionError> {
+        let secret_key = SecretKey::from_slice(private_key)
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Multisig))?;
+
+        let signer = Secp256k1::new();
+        let public_key = PublicKey::from_secret_key(&signer, &secret_key).serialize();
+
+        let message = Message::from_slice(self.hash_without_signature(prev_hash).as_ref())
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Multisig))?;
+
+        let signature = signer.sign_ecdsa(&message, &secret_key).serialize_compact();
+
+        match self
+            .signatures
+            .binary_search_by_key(&public_key, |(key, _)| *key)
+        {
+            Ok(position) => self.signatures[position].1 = signature,
+            Err(position) => self.signatures.insert(position, (public_key, signature)),
+        }
+
+        Ok(())
+    }
+    fn clone_box(&self) -> Box<dyn Transactionable> {
+        Box::new(self.clone())
+    }
+}
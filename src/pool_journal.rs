@@ -0,0 +1,239 @@
+use crate::errors::*;
+use crate::registry;
+use crate::tools;
+use crate::transaction::Transactionable;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use error_stack::{IntoReport, Result, ResultExt};
+
+// Journal is a sequence of records:
+// [kind: u8][payload size: u32][payload][checksum: 4 bytes]
+// checksum is the beginning of sha256 of kind, size and payload
+//
+// add payload:    [added at: u64][transaction dump]
+// remove payload: [transaction id: 32 bytes]
+//
+// torn record at the end of the file is a result of a crash
+// during append and is cut off on replay
+
+static ADD_RECORD: u8 = 0;
+static REMOVE_RECORD: u8 = 1;
+static RECORD_HEADER_SIZE: usize = 5;
+static CHECKSUM_SIZE: usize = 4;
+
+/// records count below which journal is never compacted
+pub static MIN_COMPACTION_RECORDS: usize = 1024;
+
+/// time transaction was added and the transaction
+pub type JournalEntry = (u64, Box<dyn Transactionable>);
+
+#[derive(Debug)]
+pub struct PoolJournal {
+    path: PathBuf,
+    file: File,
+    records: usize,
+}
+
+fn checksum(kind: u8, payload: &[u8]) -> [u8; 4] {
+    let mut data: Vec<u8> = Vec::with_capacity(RECORD_HEADER_SIZE + payload.len());
+    data.push(kind);
+    data.extend((payload.len() as u32).to_be_bytes());
+    data.extend(payload);
+
+    tools::hash(&data)[..CHECKSUM_SIZE].try_into().unwrap()
+}
+
+fn dump_record(kind: u8, payload: &[u8]) -> Vec<u8> {
+    let mut record: Vec<u8> =
+        Vec::with_capacity(RECORD_HEADER_SIZE + payload.len() + CHECKSUM_SIZE);
+    record.push(kind);
+    record.extend((payload.len() as u32).to_be_bytes());
+    record.extend(payload);
+    record.extend(checksum(kind, payload));
+    record
+}
+
+fn open_file(path: &Path) -> Result<File, PoolJournalError> {
+    OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)
+        .report()
+        .change_context(PoolJournalError::Journal(PoolJournalErrorKind::Open))
+        .attach_printable(format!("failed to open journal at {}", path.display()))
+}
+
+/// Parses transaction dump including its header with the shared registry
+pub fn parse_transaction(dump: &[u8]) -> Result<Box<dyn Transactionable>, PoolJournalError> {
+    registry::parse(dump).change_context(PoolJournalError::Journal(PoolJournalErrorKind::Replay))
+}
+
+impl PoolJournal {
+    pub fn open(path: &Path) -> Result<PoolJournal, PoolJournalError> {
+        let file = open_file(path)?;
+
+        Ok(PoolJournal {
+            path: path.to_path_buf(),
+            file,
+            records: 0,
+        })
+    }
+
+    /// Amount of records in the journal
+    pub fn get_records(&self) -> usize {
+        self.records
+    }
+
+    /// Reads live transactions from the journal in the order they were added
+    pub fn replay(&mut self) -> Result<Vec<JournalEntry>, PoolJournalError> {
+        let mut data: Vec<u8> = Vec::new();
+        File::open(&self.path)
+            .and_then(|mut file| file.read_to_end(&mut data))
+            .report()
+            .change_context(PoolJournalError::Journal(PoolJournalErrorKind::Replay))
+            .attach_printable("failed to read journal")?;
+
+        let mut added: Vec<Option<JournalEntry>> = Vec::new();
+        let mut positions: HashMap<[u8; 32], usize> = HashMap::new();
+
+        let mut records: usize = 0;
+        let mut offset: usize = 0;
+        while offset + RECORD_HEADER_SIZE <= data.len() {
+            let kind = data[offset];
+            let size =
+                u32::from_be_bytes(data[offset + 1..offset + 5].try_into().unwrap()) as usize;
+
+            let payload_start = offset + RECORD_HEADER_SIZE;
+            let record_end = payload_start + size + CHECKSUM_SIZE;
+            if record_end > data.len() {
+                break;
+            }
+
+            let payload = &data[payload_start..payload_start + size];
+            if checksum(kind, payload) != data[payload_start + size..record_end] {
+                break;
+            }
+
+            if kind == ADD_RECORD && size > 8 {
+                let added_at = u64::from_be_bytes(payload[..8].try_into().unwrap());
+                let transaction = parse_transaction(&payload[8..])?;
+
+                positions.insert(transaction.get_id(), added.len());
+                added.push(Some((added_at, transaction)));
+            } else if kind == REMOVE_RECORD && size == 32 {
+                let key: [u8; 32] = payload.try_into().unwrap();
+                if let Some(position) = positions.get(&key) {
+                    added[*position] = None;
+                }
+            } else {
+                break;
+            }
+
+            records += 1;
+            offset = record_end;
+        }
+
+        // cut off torn or corrupted tail
+        if offset != data.len() {
+            self.file
+                .set_len(offset as u64)
+                .report()
+                .change_context(PoolJournalError::Journal(PoolJournalErrorKind::Replay))
+                .attach_printable("failed to truncate torn record")?;
+        }
+        self.records = records;
+
+        Ok(added.into_iter().flatten().collect())
+    }
+
+    fn append(&mut self, kind: u8, payload: &[u8]) -> Result<(), PoolJournalError> {
+        self.file
+            .write_all(&dump_record(kind, payload))
+            .and_then(|_| self.file.sync_data())
+            .report()
+            .change_context(PoolJournalError::Journal(PoolJournalErrorKind::Append))?;
+
+        self.records += 1;
+
+        Ok(())
+    }
+
+    /// Records transaction admitted to the pool
+    pub fn append_add(
+        &mut self,
+        transaction: &dyn Transactionable,
+        added_at: u64,
+    ) -> Result<(), PoolJournalError> {
+        let dump = transaction
+            .dump()
+            .change_context(PoolJournalError::Journal(PoolJournalErrorKind::Append))?;
+
+        let mut payload: Vec<u8> = Vec::with_capacity(8 + dump.len());
+        payload.extend(added_at.to_be_bytes());
+        payload.extend(dump);
+
+        self.append(ADD_RECORD, &payload)
+    }
+
+    /// Records tombstone of the transaction with the given id
+    pub fn append_remove(&mut self, key: &[u8; 32]) -> Result<(), PoolJournalError> {
+        self.append(REMOVE_RECORD, key)
+    }
+
+    /// Whether journal has grown enough compared to amount of live transactions
+    pub fn needs_compaction(&self, live: usize) -> bool {
+        self.records >= MIN_COMPACTION_RECORDS && self.records > live * 2
+    }
+
+    /// Rewrites journal so it only contains the given transactions
+    ///
+    /// new journal is written aside and atomically renamed over the old one
+    pub fn compact<'a>(
+        &mut self,
+        transactions: impl Iterator<Item = (&'a dyn Transactionable, u64)>,
+    ) -> Result<(), PoolJournalError> {
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+
+        let mut data: Vec<u8> = Vec::new();
+        let mut records: usize = 0;
+        for (transaction, added_at) in transactions {
+            let dump = transaction
+                .dump()
+                .change_context(PoolJournalError::Journal(PoolJournalErrorKind::Compact))?;
+
+            let mut payload: Vec<u8> = Vec::with_capacity(8 + dump.len());
+            payload.extend(added_at.to_be_bytes());
+            payload.extend(dump);
+
+            data.extend(dump_record(ADD_RECORD, &payload));
+            records += 1;
+        }
+
+        File::create(&temp_path)
+            .and_then(|mut file| {
+                file.write_all(&data)?;
+                file.sync_all()
+            })
+            .report()
+            .change_context(PoolJournalError::Journal(PoolJournalErrorKind::Compact))
+            .attach_printable("failed to write compacted journal")?;
+
+        fs::rename(&temp_path, &self.path)
+            .report()
+            .change_context(PoolJournalError::Journal(PoolJournalErrorKind::Compact))
+            .attach_printable("failed to replace journal")?;
+
+        self.file = open_file(&self.path)
+            .change_context(PoolJournalError::Journal(PoolJournalErrorKind::Compact))?;
+        self.records = records;
+
+        Ok(())
+    }
+}
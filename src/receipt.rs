@@ -0,0 +1,71 @@
+use crate::block::TransactionBlock;
+use crate::merkletree;
+use crate::transaction::Transactionable;
+
+/// Proof that the transaction was included in the main chain
+#[derive(Debug)]
+pub struct Receipt {
+    block: TransactionBlock,
+    height: u64,
+    position: usize,
+    confirmations: u64,
+    proof: Vec<[u8; 32]>,
+}
+
+impl Receipt {
+    pub fn new(
+        block: TransactionBlock,
+        height: u64,
+        position: usize,
+        confirmations: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Receipt {
+        Receipt {
+            block,
+            height,
+            position,
+            confirmations,
+            proof,
+        }
+    }
+
+    pub fn get_transaction(&self) -> &dyn Transactionable {
+        self.block.get_transactions()[self.position].as_ref()
+    }
+
+    pub fn get_block(&self) -> &TransactionBlock {
+        &self.block
+    }
+
+    pub fn get_height(&self) -> u64 {
+        self.height
+    }
+
+    /// Position of the transaction in the block
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+
+    /// Amount of blocks on top of the one containing transaction, including it
+    pub fn get_confirmations(&self) -> u64 {
+        self.confirmations
+    }
+
+    pub fn get_proof(&self) -> &[[u8; 32]] {
+        &self.proof
+    }
+
+    /// Checks merkle proof against the root of the block
+    pub fn verify_proof(&self) -> bool {
+        if self.proof.is_empty() {
+            return false;
+        }
+
+        merkletree::verify_proof(
+            &self.get_transaction().get_id(),
+            self.block.get_merkle_tree_root(),
+            self.proof.iter().collect(),
+            self.position,
+        )
+    }
+}
@@ -0,0 +1,182 @@
+use crate::dump_headers::Headers;
+use crate::errors::*;
+use crate::memo::MemoTransaction;
+use crate::multisig::{MultisigRegistration, MultisigTransaction};
+use crate::token::{Token, TokenAction};
+use crate::transaction::{Transaction, Transactionable};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use error_stack::{Report, Result};
+
+/// Parses transaction body, the dump without the header byte
+pub type ParseFn = fn(&[u8], u64) -> Result<Box<dyn Transactionable>, TransactionError>;
+
+fn boxed<T: Transactionable + 'static>(
+    parsed: Result<T, TransactionError>,
+) -> Result<Box<dyn Transactionable>, TransactionError> {
+    parsed.map(|transaction| Box::new(transaction) as Box<dyn Transactionable>)
+}
+
+fn parse_legacy(data: &[u8], size: u64) -> Result<Box<dyn Transactionable>, TransactionError> {
+    boxed(Transaction::parse(data, size))
+}
+
+fn parse_versioned(data: &[u8], size: u64) -> Result<Box<dyn Transactionable>, TransactionError> {
+    boxed(Transaction::parse_versioned(data, size))
+}
+
+fn parse_multisig(data: &[u8], size: u64) -> Result<Box<dyn Transactionable>, TransactionError> {
+    boxed(MultisigTransaction::parse(data, size))
+}
+
+fn parse_multisig_registration(
+    data: &[u8],
+    size: u64,
+) -> Result<Box<dyn Transactionable>, TransactionError> {
+    boxed(MultisigRegistration::parse(data, size))
+}
+
+fn parse_token(data: &[u8], size: u64) -> Result<Box<dyn Transactionable>, TransactionError> {
+    boxed(<Token as Transactionable>::parse(data, size))
+}
+
+fn parse_token_action(
+    data: &[u8],
+    size: u64,
+) -> Result<Box<dyn Transactionable>, TransactionError> {
+    boxed(TokenAction::parse(data, size))
+}
+
+fn parse_memo(data: &[u8], size: u64) -> Result<Box<dyn Transactionable>, TransactionError> {
+    boxed(MemoTransaction::parse(data, size))
+}
+
+/// Parse functions of transaction kinds by their header byte
+#[derive(Debug, Clone)]
+pub struct TransactionRegistry {
+    parsers: HashMap<u8, ParseFn>,
+}
+
+impl Default for TransactionRegistry {
+    /// Registry with the transaction kinds of this crate
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry
+            .parsers
+            .insert(Headers::Transaction as u8, parse_legacy);
+        registry
+            .parsers
+            .insert(Headers::VersionedTransaction as u8, parse_versioned);
+        registry
+            .parsers
+            .insert(Headers::MultisigTransaction as u8, parse_multisig);
+        registry.parsers.insert(
+            Headers::MultisigRegistration as u8,
+            parse_multisig_registration,
+        );
+        registry
+            .parsers
+            .insert(Headers::MemoTransaction as u8, parse_memo);
+        registry.parsers.insert(Headers::Token as u8, parse_token);
+        registry
+            .parsers
+            .insert(Headers::TokenAction as u8, parse_token_action);
+        registry
+    }
+}
+
+impl TransactionRegistry {
+    /// Empty registry
+    pub fn new() -> TransactionRegistry {
+        TransactionRegistry {
+            parsers: HashMap::new(),
+        }
+    }
+
+    /// Registers parse function of the transaction kind
+    ///
+    /// header can't be taken by another kind
+    pub fn register(&mut self, header: u8, parse: ParseFn) -> Result<(), TransactionError> {
+        if self.parsers.contains_key(&header) {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Unregistered))
+                .attach_printable(format!("header {} is already registered", header)));
+        }
+        self.parsers.insert(header, parse);
+
+        Ok(())
+    }
+
+    pub fn is_registered(&self, header: u8) -> bool {
+        self.parsers.contains_key(&header)
+    }
+
+    /// Parses transaction body of the kind with the given header
+    pub fn parse_body(
+        &self,
+        header: u8,
+        data: &[u8],
+        size: u64,
+    ) -> Result<Box<dyn Transactionable>, TransactionError> {
+        let parse = match self.parsers.get(&header) {
+            Some(parse) => parse,
+            None => {
+                return Err(Report::new(TransactionError::Tx(TxErrorKind::Unregistered))
+                    .attach_printable(format!("no transaction kind with header {}", header)));
+            }
+        };
+
+        parse(data, size)
+    }
+
+    /// Parses transaction dump starting with the header
+    pub fn parse(&self, dump: &[u8]) -> Result<Box<dyn Transactionable>, TransactionError> {
+        if dump.is_empty() {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("empty transaction dump"));
+        }
+
+        self.parse_body(dump[0], &dump[1..], (dump.len() - 1) as u64)
+    }
+}
+
+/// Registry shared by blocks and the pool
+fn global() -> &'static RwLock<TransactionRegistry> {
+    static REGISTRY: OnceLock<RwLock<TransactionRegistry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(TransactionRegistry::default()))
+}
+
+/// Registers transaction kind, so it's parsed inside blocks and the pool journal
+pub fn register(header: u8, parse: ParseFn) -> Result<(), TransactionError> {
+    global()
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .register(header, parse)
+}
+
+pub fn is_registered(header: u8) -> bool {
+    global()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .is_registered(header)
+}
+
+/// Parses transaction body with the shared registry
+pub fn parse_body(
+    header: u8,
+    data: &[u8],
+    size: u64,
+) -> Result<Box<dyn Transactionable>, TransactionError> {
+    global()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .parse_body(header, data, size)
+}
+
+/// Parses transaction dump with the shared registry
+pub fn parse(dump: &[u8]) -> Result<Box<dyn Transactionable>, TransactionError> {
+    global()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .parse(dump)
+}
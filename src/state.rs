@@ -0,0 +1,180 @@
+use crate::errors::*;
+use crate::merkletree::hash_pair;
+use crate::tools;
+use num_bigint::BigUint;
+use num_traits::Zero;
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+use error_stack::{IntoReport, Result, ResultExt};
+
+/*
+    State root protocol
+
+    Every non empty account contributes a leaf
+    leaf = sha256(address || dumped balance || nonce)
+
+    Leafs are put into a sparse merkle tree of depth 256,
+    path of the leaf is sha256(address) read from the most significant bit,
+    0 goes to the left child, 1 to the right one.
+
+    empty leaf = 32 zero bytes
+    node = sha256(left child || right child)
+    state root = node at depth 0
+
+    Only nodes differing from the empty subtree of their depth are stored,
+    so updating an account touches the 256 nodes on its path
+
+    Node key protocol
+
+    Prefix - 1 byte, 'S'
+    Depth - 2 bytes, big endian
+    Path - 32 bytes, bits below the depth are zeroed
+*/
+
+static STATE_NODE_PREFIX: u8 = b'S';
+static TREE_DEPTH: usize = 256;
+static EMPTY_LEAF: [u8; 32] = [0; 32];
+/// largest balance that can be dumped into the summary db
+pub static MAX_BALANCE_BYTES: usize = 255;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Account {
+    pub balance: BigUint,
+    /// nonce expected in the next transaction of the account
+    pub nonce: u64,
+}
+
+impl Account {
+    pub fn new(balance: BigUint, nonce: u64) -> Account {
+        Account { balance, nonce }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.balance.is_zero() && self.nonce == 0
+    }
+}
+
+/// Whether the balance fits into the summary db
+pub fn is_balance_storable(balance: &BigUint) -> bool {
+    balance.bits() <= (MAX_BALANCE_BYTES * 8) as u64
+}
+
+pub fn state_leaf(addr: &[u8; 33], account: &Account) -> Result<Option<[u8; 32]>, StateError> {
+    if account.is_empty() {
+        return Ok(None);
+    }
+
+    let mut concatenated: Vec<u8> =
+        Vec::with_capacity(33 + tools::bigint_size(&account.balance) + 8);
+    concatenated.extend(addr.iter());
+    tools::dump_biguint(&account.balance, &mut concatenated)
+        .change_context(StateError::State(StateErrorKind::Leaf))?;
+    concatenated.extend(account.nonce.to_be_bytes().iter());
+
+    Ok(Some(tools::hash(&concatenated)))
+}
+
+fn node_key(depth: usize, path: &[u8; 32]) -> [u8; 35] {
+    let mut key = [0u8; 35];
+    key[0] = STATE_NODE_PREFIX;
+    key[1..3].copy_from_slice(&(depth as u16).to_be_bytes());
+
+    // keep only the bits above the depth
+    let full_bytes = depth / 8;
+    key[3..3 + full_bytes].copy_from_slice(&path[..full_bytes]);
+    if !depth.is_multiple_of(8) {
+        key[3 + full_bytes] = path[full_bytes] & (0xff << (8 - depth % 8));
+    }
+
+    key
+}
+
+fn path_bit(path: &[u8; 32], index: usize) -> bool {
+    path[index / 8] & (0x80 >> (index % 8)) != 0
+}
+
+/// Sparse merkle tree of the accounts stored next to them in the summary db
+///
+/// changes are kept in memory until they are written into a batch
+pub struct StateTree {
+    nodes: sled::Tree,
+    changes: HashMap<[u8; 35], [u8; 32]>,
+    /// roots of the empty subtrees by depth
+    empty: Vec<[u8; 32]>,
+}
+
+impl StateTree {
+    pub fn new(nodes: &sled::Tree) -> StateTree {
+        let mut empty = vec![EMPTY_LEAF; TREE_DEPTH + 1];
+        for depth in (0..TREE_DEPTH).rev() {
+            empty[depth] = hash_pair(&empty[depth + 1], &empty[depth + 1]);
+        }
+
+        StateTree {
+            nodes: nodes.clone(),
+            changes: HashMap::new(),
+            empty,
+        }
+    }
+
+    fn get_node(&self, depth: usize, path: &[u8; 32]) -> Result<[u8; 32], StateError> {
+        let key = node_key(depth, path);
+        if let Some(node) = self.changes.get(&key) {
+            return Ok(*node);
+        }
+
+        let stored = self
+            .nodes
+            .get(key)
+            .report()
+            .change_context(StateError::State(StateErrorKind::Storage))?;
+
+        match stored {
+            None => Ok(self.empty[depth]),
+            Some(node) => node
+                .as_ref()
+                .try_into()
+                .report()
+                .change_context(StateError::State(StateErrorKind::Storage)),
+        }
+    }
+
+    /// Sets the state of the account, empty accounts are dropped from the tree
+    pub fn insert(&mut self, addr: &[u8; 33], account: &Account) -> Result<(), StateError> {
+        let path = tools::hash(addr);
+        let mut node = state_leaf(addr, account)?.unwrap_or(EMPTY_LEAF);
+        self.changes.insert(node_key(TREE_DEPTH, &path), node);
+
+        for depth in (0..TREE_DEPTH).rev() {
+            let mut sibling_path = path;
+            sibling_path[depth / 8] ^= 0x80 >> (depth % 8);
+            let sibling = self.get_node(depth + 1, &sibling_path)?;
+
+            node = if path_bit(&path, depth) {
+                hash_pair(&sibling, &node)
+            } else {
+                hash_pair(&node, &sibling)
+            };
+            self.changes.insert(node_key(depth, &path), node);
+        }
+
+        Ok(())
+    }
+
+    pub fn root(&self) -> Result<[u8; 32], StateError> {
+        self.get_node(0, &[0u8; 32])
+    }
+
+    /// Puts changed nodes into the batch of the summary db
+    pub fn write(&self, batch: &mut sled::Batch) {
+        for (key, node) in self.changes.iter() {
+            let depth = u16::from_be_bytes([key[1], key[2]]) as usize;
+            if *node == self.empty[depth] {
+                batch.remove(key as &[u8]);
+            } else {
+                batch.insert(key as &[u8], node as &[u8]);
+            }
+        }
+    }
+}
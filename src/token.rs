@@ -0,0 +1,833 @@
+use crate::dump_headers::Headers;
+use crate::errors::*;
+use crate::tools;
+use crate::transaction::Transactionable;
+use num_bigint::BigUint;
+use num_traits::identities::Zero;
+use secp256k1::ecdsa::Signature;
+use secp256k1::PublicKey;
+use secp256k1::{Message, Secp256k1, SecretKey, VerifyOnly};
+use sha2::{Digest, Sha256};
+use std::convert::TryInto;
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+/*
+    Token dumping protocol
+
+    Header (Headers::Token) - 1 byte
+    Current owner - 33 bytes
+    Token hash - 32 bytes
+    Signature - 64 bytes
+    Nonce - 8 bytes
+    Fee - biguint
+    Assigned - 1 byte
+
+    assigned tokens continue with:
+    Token data size - 2 bytes
+    Token data - utf-8 string
+    Smol contract size - 2 bytes
+    Smol contract - utf-8 string
+    Transfer fee - biguint
+    Coin supply - biguint
+
+    Token is signed by the current owner, who pays the fee with the nonce
+    like transfers do
+*/
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    current_owner: [u8; 33],
+
+    signature: [u8; 64],
+    token_hash: [u8; 32],
+
+    token_data: String,
+    smol_contract: String,
+
+    coin_supply: BigUint,
+    transfer_fee: BigUint,
+
+    assigned: bool,
+
+    nonce: u64,
+    fee: BigUint,
+}
+
+impl Token {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        current_owner: [u8; 33],
+        signature: [u8; 64],
+        token_hash: [u8; 32],
+        token_data: String,
+        smol_contract: String,
+        coin_supply: BigUint,
+        transfer_fee: BigUint,
+        assigned: bool,
+    ) -> Result<Token, TokenError> {
+        if !assigned {
+            if !token_data.is_empty() {
+                return Err(Report::new(TokenError::Token(TokenErrorKind::Creation))
+                    .attach_printable("Token data is already set"));
+            }
+            if !coin_supply.is_zero() {
+                return Err(Report::new(TokenError::Token(TokenErrorKind::Creation))
+                    .attach_printable("Coin supply is already set"));
+            }
+            if !smol_contract.is_empty() {
+                return Err(Report::new(TokenError::Token(TokenErrorKind::Creation))
+                    .attach_printable("Token is not assigned, but contract is set"));
+            }
+            if !transfer_fee.is_zero() {
+                return Err(Report::new(TokenError::Token(TokenErrorKind::Creation))
+                    .attach_printable("Token is not assigned, but fee is set"));
+            }
+        }
+
+        // the token has to be dumpable to be signed
+        if token_data.len() > u16::MAX as usize || smol_contract.len() > u16::MAX as usize {
+            return Err(Report::new(TokenError::Token(TokenErrorKind::Creation))
+                .attach_printable("Token data or contract is too long"));
+        }
+        if tools::bigint_size(&coin_supply) > 256 || tools::bigint_size(&transfer_fee) > 256 {
+            return Err(Report::new(TokenError::Token(TokenErrorKind::Creation))
+                .attach_printable("Coin supply or transfer fee is too big"));
+        }
+
+        let token: Token = Token {
+            current_owner,
+            signature,
+            token_hash,
+            token_data,
+            smol_contract,
+            coin_supply,
+            transfer_fee,
+            assigned,
+            nonce: 0,
+            fee: BigUint::zero(),
+        };
+
+        Ok(token)
+    }
+
+    /// Sets the nonce of the owner issuing the token
+    pub fn with_nonce(mut self, nonce: u64) -> Token {
+        self.nonce = nonce;
+        self
+    }
+
+    /// Sets the fee paid by the owner issuing the token
+    pub fn with_fee(mut self, fee: BigUint) -> Result<Token, TokenError> {
+        if tools::bigint_size(&fee) > 256 {
+            return Err(Report::new(TokenError::Token(TokenErrorKind::Creation))
+                .attach_printable("Fee is too big"));
+        }
+        self.fee = fee;
+        Ok(self)
+    }
+    pub fn is_fee_static(&self) -> bool {
+        !self.transfer_fee.is_zero()
+    }
+
+    pub fn get_current_owner(&self) -> &[u8; 33] {
+        &self.current_owner
+    }
+
+    pub fn get_token_hash(&self) -> &[u8; 32] {
+        &self.token_hash
+    }
+
+    pub fn get_token_data(&self) -> &str {
+        &self.token_data
+    }
+
+    pub fn get_smol_contract(&self) -> &str {
+        &self.smol_contract
+    }
+
+    pub fn get_coin_supply(&self) -> &BigUint {
+        &self.coin_supply
+    }
+
+    pub fn get_transfer_fee(&self) -> &BigUint {
+        &self.transfer_fee
+    }
+
+    pub fn is_assigned(&self) -> bool {
+        self.assigned
+    }
+
+    /// hash of the data signed by the owner
+    ///
+    /// the dump of the token without the header and the signature
+    fn signed_data_hash(&self) -> Result<[u8; 32], TokenError> {
+        let mut signed_data: Vec<u8> = Vec::with_capacity(self.get_dump_size() - 1 - 64);
+
+        signed_data.extend(self.current_owner.iter());
+        signed_data.extend(self.token_hash.iter());
+        self.dump_fields(&mut signed_data)?;
+
+        // getting hash of data
+        let mut hasher = Sha256::new();
+        hasher.update(signed_data);
+        Ok(hasher.finalize().into())
+    }
+
+    fn verify_with(&self, verifier: &Secp256k1<VerifyOnly>) -> Result<bool, TokenError> {
+        let signed_data = self
+            .signed_data_hash()
+            .change_context(TokenError::Token(TokenErrorKind::Verify))?;
+
+        // loading message
+        let message = Message::from_slice(&signed_data)
+            .report()
+            .attach_printable("Error verifying token: couldn't load message")
+            .change_context(TokenError::Token(TokenErrorKind::Verify))?;
+
+        // loading public key
+        let public_key = PublicKey::from_slice(&self.current_owner)
+            .report()
+            .attach_printable("Error verifying token: couldn't load public key")
+            .change_context(TokenError::Token(TokenErrorKind::Verify))?;
+
+        // load signature
+        let signature = Signature::from_compact(&self.signature)
+            .report()
+            .attach_printable("Error verifying token: couldn't load signature")
+            .change_context(TokenError::Token(TokenErrorKind::Verify))?;
+
+        match verifier.verify_ecdsa(&message, &signature, &public_key) {
+            Err(_) => Ok(false),
+            Ok(_) => Ok(true),
+        }
+    }
+
+    fn dump_string(string: &str, buffer: &mut Vec<u8>) -> Result<(), TokenError> {
+        if string.len() > u16::MAX as usize {
+            return Err(Report::new(TokenError::Token(TokenErrorKind::Dump))
+                .attach_printable(format!("string of {} bytes is too long", string.len())));
+        }
+
+        buffer.extend((string.len() as u16).to_be_bytes().iter());
+        buffer.extend(string.as_bytes().iter());
+
+        Ok(())
+    }
+
+    /// Dumps fields following the signature
+    fn dump_fields(&self, buffer: &mut Vec<u8>) -> Result<(), TokenError> {
+        buffer.extend(self.nonce.to_be_bytes().iter());
+
+        tools::dump_biguint(&self.fee, buffer)
+            .attach_printable("Error dumping token: couldn't dump fee")
+            .change_context(TokenError::Token(TokenErrorKind::Dump))?;
+
+        if !self.assigned {
+            buffer.push(0);
+            return Ok(());
+        }
+        buffer.push(1);
+
+        Self::dump_string(&self.token_data, buffer)
+            .attach_printable("Error dumping token: couldn't dump token data")?;
+
+        Self::dump_string(&self.smol_contract, buffer)
+            .attach_printable("Error dumping token: couldn't dump smol contract")?;
+
+        tools::dump_biguint(&self.transfer_fee, buffer)
+            .attach_printable("Error dumping token: couldn't dump transfer fee")
+            .change_context(TokenError::Token(TokenErrorKind::Dump))?;
+
+        tools::dump_biguint(&self.coin_supply, buffer)
+            .attach_printable("Error dumping token: couldn't dump coin supply")
+            .change_context(TokenError::Token(TokenErrorKind::Dump))?;
+
+        Ok(())
+    }
+
+    fn parse_string(data: &[u8]) -> Result<(String, usize), TokenError> {
+        if data.len() < 2 {
+            return Err(Report::new(TokenError::Token(TokenErrorKind::Parse))
+                .attach_printable("Data length < 2"));
+        }
+        let size = u16::from_be_bytes(data[..2].try_into().unwrap()) as usize;
+
+        if data.len() < 2 + size {
+            return Err(Report::new(TokenError::Token(TokenErrorKind::Parse))
+                .attach_printable("Couldn't find the end of string"));
+        }
+
+        let string = String::from_utf8(data[2..2 + size].to_vec())
+            .report()
+            .change_context(TokenError::Token(TokenErrorKind::Parse))?;
+
+        Ok((string, 2 + size))
+    }
+}
+
+/// Tokens are carried by transaction blocks under `Headers::Token`
+impl Transactionable for Token {
+    fn hash(&self, prev_hash: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+
+        hasher.update(prev_hash);
+        hasher.update(Transactionable::get_id(self));
+        hasher.update(self.signature);
+
+        hasher.finalize().into()
+    }
+
+    fn hash_without_signature(&self, _prev_hash: &[u8; 32]) -> Box<[u8; 32]> {
+        Box::new(
+            self.signed_data_hash()
+                .expect("token fields are checked to be dumpable on creation"),
+        )
+    }
+
+    fn get_id(&self) -> [u8; 32] {
+        *self.hash_without_signature(&[0u8; 32])
+    }
+
+    fn verify_with_context(
+        &self,
+        _prev_hash: &[u8; 32],
+        verifier: &Secp256k1<VerifyOnly>,
+    ) -> Result<bool, TransactionError> {
+        self.verify_with(verifier)
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))
+    }
+
+    fn dump(&self) -> Result<Vec<u8>, TransactionError> {
+        let calculated_size: usize = self.get_dump_size();
+
+        let mut dumped_token: Vec<u8> = Vec::with_capacity(calculated_size);
+
+        // header
+        dumped_token.push(Headers::Token as u8);
+
+        dumped_token.extend(self.current_owner.iter());
+        dumped_token.extend(self.token_hash.iter());
+        dumped_token.extend(self.signature.iter());
+
+        self.dump_fields(&mut dumped_token)
+            .change_context(TransactionError::Tx(TxErrorKind::Dump))?;
+
+        Ok(dumped_token)
+    }
+
+    fn get_dump_size(&self) -> usize {
+        let mut size: usize = 1 // header
+            + 33 // current owner
+            + 32 // token hash
+            + 64 // signature
+            + 8 // nonce
+            + tools::bigint_size(&self.fee)
+            + 1; // is assigned
+
+        if self.assigned {
+            size += 2
+                + self.token_data.len()
+                + 2
+                + self.smol_contract.len()
+                + tools::bigint_size(&self.transfer_fee)
+                + tools::bigint_size(&self.coin_supply);
+        }
+
+        size
+    }
+
+    fn parse(data: &[u8], token_size: u64) -> Result<Token, TransactionError> {
+        if data.len() < 33 + 32 + 64 + 8 + 1 {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Data length < 138"));
+        }
+        let mut index: usize = 0;
+
+        // parsing current owner address
+        let current_owner: [u8; 33] = data[index..index + 33].try_into().unwrap();
+        index += 33;
+
+        // parsing token hash
+        let token_hash: [u8; 32] = data[index..index + 32].try_into().unwrap();
+        index += 32;
+
+        // parsing signature
+        let signature: [u8; 64] = data[index..index + 64].try_into().unwrap();
+        index += 64;
+
+        // parsing nonce
+        let nonce = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
+        index += 8;
+
+        // parsing fee
+        let (fee, idx) = tools::load_biguint(&data[index..])
+            .attach_printable("Couldn't parse fee")
+            .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+        index += idx;
+
+        // parsing assigned flag
+        if data.len() <= index {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse assigned flag"));
+        }
+        let assigned = match data[index] {
+            0 => false,
+            1 => true,
+            _ => {
+                return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                    .attach_printable("Wrong assigned flag"));
+            }
+        };
+        index += 1;
+
+        let mut token_data = String::new();
+        let mut smol_contract = String::new();
+        let mut transfer_fee = BigUint::zero();
+        let mut coin_supply = BigUint::zero();
+
+        if assigned {
+            // parsing token data
+            let (string, idx) = Token::parse_string(&data[index..])
+                .attach_printable("Couldn't parse token data")
+                .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+            token_data = string;
+            index += idx;
+
+            // parsing smol contract
+            let (string, idx) = Token::parse_string(&data[index..])
+                .attach_printable("Couldn't parse smol contract")
+                .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+            smol_contract = string;
+            index += idx;
+
+            // parsing transfer fee
+            if data.len() <= index {
+                return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                    .attach_printable("Couldn't parse transfer fee"));
+            }
+            let (fee, idx) = tools::load_biguint(&data[index..])
+                .attach_printable("Couldn't parse transfer fee")
+                .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+            transfer_fee = fee;
+            index += idx;
+
+            // parsing coin supply
+            if data.len() <= index {
+                return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                    .attach_printable("Couldn't parse coin supply"));
+            }
+            let (supply, idx) = tools::load_biguint(&data[index..])
+                .attach_printable("Couldn't parse coin supply")
+                .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+            coin_supply = supply;
+            index += idx;
+        }
+
+        if index != token_size as usize {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Wrong size of token"));
+        }
+
+        Token::new(
+            current_owner,
+            signature,
+            token_hash,
+            token_data,
+            smol_contract,
+            coin_supply,
+            transfer_fee,
+            assigned,
+        )
+        .and_then(|token| token.with_nonce(nonce).with_fee(fee))
+        .change_context(TransactionError::Tx(TxErrorKind::Parse))
+    }
+
+    fn get_sender(&self) -> &[u8; 33] {
+        &self.current_owner
+    }
+
+    fn get_receiver(&self) -> &[u8; 33] {
+        &self.current_owner
+    }
+
+    fn get_timestamp(&self) -> u64 {
+        0
+    }
+
+    fn get_signature(&self) -> &[u8; 64] {
+        &self.signature
+    }
+
+    fn get_amount(&self) -> Option<&BigUint> {
+        None
+    }
+
+    fn get_nonce(&self) -> Option<u64> {
+        Some(self.nonce)
+    }
+
+    fn get_fee(&self) -> Option<&BigUint> {
+        Some(&self.fee)
+    }
+
+    fn get_lock_time(&self) -> Option<u64> {
+        None
+    }
+
+    fn get_payload(&self) -> Option<&[u8]> {
+        None
+    }
+
+    fn get_expiry_height(&self) -> Option<u64> {
+        None
+    }
+
+    fn get_token(&self) -> Option<&Token> {
+        Some(self)
+    }
+
+    fn sign(
+        &mut self,
+        _prev_hash: &[u8; 32],
+        private_key: &[u8; 32],
+    ) -> Result<(), TransactionError> {
+        let signed_data = self
+            .signed_data_hash()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let message = Message::from_slice(&signed_data)
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let secret_key = SecretKey::from_slice(private_key)
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let signer = Secp256k1::new();
+
+        self.signature = signer.sign_ecdsa(&message, &secret_key).serialize_compact();
+
+        Ok(())
+    }
+    fn clone_box(&self) -> Box<dyn Transactionable> {
+        Box::new(self.clone())
+    }
+}
+
+/*
+    Token action dumping protocol
+
+    Header (Headers::TokenAction) - 1 byte
+    Action - 1 byte
+    Previous owner - 33 bytes
+    Current owner - 33 bytes
+    Token hash - 32 bytes
+    Timestamp - 8 bytes
+    Nonce - 8 bytes
+    Fee - biguint
+    Signature - 64 bytes
+
+    Action is signed by the previous owner, who pays the fee with the nonce
+    like transfers do,
+    burned tokens have no current owner, the field is ignored
+    and the previous owner is the receiver of the action
+*/
+
+/// size of the token action without the fee
+static TOKEN_ACTION_SIZE: usize = 1 + 1 + 33 + 33 + 32 + 8 + 8 + 64;
+
+#[derive(Debug, Clone)]
+pub struct TokenAction {
+    action: Action,
+    current_owner: [u8; 33],
+    previous_owner: [u8; 33],
+    signature: [u8; 64],
+    token_hash: [u8; 32],
+    timestamp: u64,
+    nonce: u64,
+    fee: BigUint,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    Send = 1,
+    Assign,
+    Burn,
+}
+
+impl Action {
+    pub fn from_u8(action: u8) -> Result<Action, TokenError> {
+        match action {
+            1 => Ok(Action::Send),
+            2 => Ok(Action::Assign),
+            3 => Ok(Action::Burn),
+            _ => Err(Report::new(TokenError::Token(TokenErrorKind::Parse))
+                .attach_printable(format!("unknown token action {}", action))),
+        }
+    }
+}
+
+impl TokenAction {
+    pub fn new(
+        action: Action,
+        current_owner: &[u8; 33],
+        previous_owner: &[u8; 33],
+        signature: &[u8; 64],
+        token_hash: [u8; 32],
+        timestamp: u64,
+        nonce: u64,
+    ) -> TokenAction {
+        TokenAction {
+            action,
+            current_owner: *current_owner,
+            previous_owner: *previous_owner,
+            signature: *signature,
+            token_hash,
+            timestamp,
+            nonce,
+            fee: BigUint::zero(),
+        }
+    }
+
+    /// Sets the fee paid by the previous owner
+    pub fn with_fee(mut self, fee: BigUint) -> Result<TokenAction, TokenError> {
+        if tools::bigint_size(&fee) > 256 {
+            return Err(Report::new(TokenError::Token(TokenErrorKind::Creation))
+                .attach_printable("Fee is too big"));
+        }
+        self.fee = fee;
+        Ok(self)
+    }
+
+    pub fn get_action(&self) -> Action {
+        self.action
+    }
+
+    pub fn get_current_owner(&self) -> &[u8; 33] {
+        &self.current_owner
+    }
+
+    pub fn get_previous_owner(&self) -> &[u8; 33] {
+        &self.previous_owner
+    }
+
+    pub fn get_token_hash(&self) -> &[u8; 32] {
+        &self.token_hash
+    }
+
+    /// data covered by the signature
+    fn signed_data(&self) -> Vec<u8> {
+        let mut concatenated: Vec<u8> = Vec::with_capacity(self.get_dump_size() - 64);
+
+        concatenated.push(Headers::TokenAction as u8);
+        concatenated.push(self.action as u8);
+        concatenated.extend(self.previous_owner.iter());
+        concatenated.extend(self.current_owner.iter());
+        concatenated.extend(self.token_hash.iter());
+        concatenated.extend(self.timestamp.to_be_bytes().iter());
+        concatenated.extend(self.nonce.to_be_bytes().iter());
+        // fee size is checked on creation
+        tools::dump_biguint(&self.fee, &mut concatenated).unwrap();
+
+        concatenated
+    }
+}
+
+impl Transactionable for TokenAction {
+    fn hash(&self, prev_hash: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+
+        hasher.update(prev_hash);
+        hasher.update(self.signed_data());
+        hasher.update(self.signature);
+
+        hasher.finalize().into()
+    }
+
+    fn hash_without_signature(&self, _prev_hash: &[u8; 32]) -> Box<[u8; 32]> {
+        Box::new(tools::hash(&self.signed_data()))
+    }
+
+    fn get_id(&self) -> [u8; 32] {
+        tools::hash(&self.signed_data())
+    }
+
+    fn verify_with_context(
+        &self,
+        prev_hash: &[u8; 32],
+        verifier: &Secp256k1<VerifyOnly>,
+    ) -> Result<bool, TransactionError> {
+        let message = Message::from_slice(self.hash_without_signature(prev_hash).as_ref())
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let sender = PublicKey::from_slice(&self.previous_owner)
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let signature = Signature::from_compact(&self.signature)
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        Ok(verifier.verify_ecdsa(&message, &signature, &sender).is_ok())
+    }
+
+    fn dump(&self) -> Result<Vec<u8>, TransactionError> {
+        let mut dumped_action: Vec<u8> = Vec::with_capacity(self.get_dump_size());
+
+        // header
+        dumped_action.push(Headers::TokenAction as u8);
+
+        dumped_action.push(self.action as u8);
+        dumped_action.extend(self.previous_owner.iter());
+        dumped_action.extend(self.current_owner.iter());
+        dumped_action.extend(self.token_hash.iter());
+        dumped_action.extend(self.timestamp.to_be_bytes().iter());
+        dumped_action.extend(self.nonce.to_be_bytes().iter());
+        tools::dump_biguint(&self.fee, &mut dumped_action)
+            .attach_printable("Error dumping token action: couldn't dump fee")
+            .change_context(TransactionError::Tx(TxErrorKind::Dump))?;
+        dumped_action.extend(self.signature.iter());
+
+        Ok(dumped_action)
+    }
+
+    fn get_dump_size(&self) -> usize {
+        TOKEN_ACTION_SIZE + tools::bigint_size(&self.fee)
+    }
+
+    fn parse(data: &[u8], size: u64) -> Result<TokenAction, TransactionError> {
+        if data.len() < TOKEN_ACTION_SIZE - 1 {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Wrong size of token action"));
+        }
+        let mut index: usize = 0;
+
+        // parsing action
+        let action = Action::from_u8(data[index])
+            .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+        index += 1;
+
+        // parsing previous owner
+        let previous_owner: [u8; 33] = data[index..index + 33].try_into().unwrap();
+        index += 33;
+
+        // parsing current owner
+        let current_owner: [u8; 33] = data[index..index + 33].try_into().unwrap();
+        index += 33;
+
+        // parsing token hash
+        let token_hash: [u8; 32] = data[index..index + 32].try_into().unwrap();
+        index += 32;
+
+        // parsing timestamp
+        let timestamp = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
+        index += 8;
+
+        // parsing nonce
+        let nonce = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
+        index += 8;
+
+        // parsing fee
+        let (fee, idx) = tools::load_biguint(&data[index..])
+            .attach_printable("Couldn't parse fee")
+            .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+        index += idx;
+
+        // parsing signature
+        if data.len() < index + 64 {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse signature"));
+        }
+        let signature: [u8; 64] = data[index..index + 64].try_into().unwrap();
+        index += 64;
+
+        if index != size as usize {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Wrong size of token action"));
+        }
+
+        Ok(TokenAction {
+            action,
+            current_owner,
+            previous_owner,
+            signature,
+            token_hash,
+            timestamp,
+            nonce,
+            fee,
+        })
+    }
+
+    fn get_sender(&self) -> &[u8; 33] {
+        &self.previous_owner
+    }
+
+    fn get_receiver(&self) -> &[u8; 33] {
+        // ignored owner of the burned token isn't touched by the action
+        match self.action {
+            Action::Burn => &self.previous_owner,
+            _ => &self.current_owner,
+        }
+    }
+
+    fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    fn get_signature(&self) -> &[u8; 64] {
+        &self.signature
+    }
+
+    fn get_amount(&self) -> Option<&BigUint> {
+        None
+    }
+
+    fn get_nonce(&self) -> Option<u64> {
+        Some(self.nonce)
+    }
+
+    fn get_fee(&self) -> Option<&BigUint> {
+        Some(&self.fee)
+    }
+
+    fn get_lock_time(&self) -> Option<u64> {
+        None
+    }
+
+    fn get_payload(&self) -> Option<&[u8]> {
+        None
+    }
+
+    fn get_expiry_height(&self) -> Option<u64> {
+        None
+    }
+
+    fn get_token_action(&self) -> Option<&TokenAction> {
+        Some(self)
+    }
+
+    fn sign(
+        &mut self,
+        prev_hash: &[u8; 32],
+        private_key: &[u8; 32],
+    ) -> Result<(), TransactionError> {
+        let message = Message::from_slice(self.hash_without_signature(prev_hash).as_ref())
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let secret_key = SecretKey::from_slice(private_key)
+            .report()
+            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+        let signer = Secp256k1::new();
+
+        self.signature = signer.sign_ecdsa(&message, &secret_key).serialize_compact();
+
+        Ok(())
+    }
+    fn clone_box(&self) -> Box<dyn Transactionable> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,281 @@
+use crate::errors::*;
+use crate::token::{Action, Token, TokenAction};
+use crate::transaction::Transactionable;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::path::Path;
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+/*
+    Token ledger
+
+    tokens tree: token hash (32 bytes) -> token state
+    owners tree: owner (33 bytes) + token hash (32 bytes) -> empty
+
+    Token state dumping protocol
+
+    Owner - 33 bytes
+    Flags - 1 byte, ASSIGNED_FLAG | BURNED_FLAG
+
+    Burned tokens are kept to reject further actions,
+    but they don't belong to anyone in the owners tree
+*/
+
+static OWNERS_TREE: &[u8] = b"OWNERS";
+static ASSIGNED_FLAG: u8 = 1;
+static BURNED_FLAG: u8 = 2;
+static TOKEN_STATE_SIZE: usize = 33 + 1;
+
+/// Ownership and state of a token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenState {
+    owner: [u8; 33],
+    assigned: bool,
+    burned: bool,
+}
+
+/// Changed tokens with their previous and new states
+pub type TokenChanges = HashMap<[u8; 32], (Option<TokenState>, TokenState)>;
+
+fn action_error(message: &'static str) -> Report<TokenError> {
+    Report::new(TokenError::Token(TokenErrorKind::Action)).attach_printable(message)
+}
+
+fn owner_key(owner: &[u8; 33], token_hash: &[u8; 32]) -> [u8; 65] {
+    let mut key = [0u8; 65];
+    key[..33].copy_from_slice(owner);
+    key[33..].copy_from_slice(token_hash);
+    key
+}
+
+impl TokenState {
+    pub fn new(owner: [u8; 33], assigned: bool, burned: bool) -> TokenState {
+        TokenState {
+            owner,
+            assigned,
+            burned,
+        }
+    }
+
+    pub fn get_owner(&self) -> &[u8; 33] {
+        &self.owner
+    }
+
+    pub fn is_assigned(&self) -> bool {
+        self.assigned
+    }
+
+    pub fn is_burned(&self) -> bool {
+        self.burned
+    }
+
+    pub fn dump(&self) -> Vec<u8> {
+        let mut dump: Vec<u8> = Vec::with_capacity(TOKEN_STATE_SIZE);
+        dump.extend(self.owner.iter());
+
+        let mut flags = 0;
+        if self.assigned {
+            flags |= ASSIGNED_FLAG;
+        }
+        if self.burned {
+            flags |= BURNED_FLAG;
+        }
+        dump.push(flags);
+
+        dump
+    }
+
+    pub fn parse(data: &[u8]) -> Result<TokenState, TokenError> {
+        if data.len() != TOKEN_STATE_SIZE {
+            return Err(Report::new(TokenError::Token(TokenErrorKind::Parse))
+                .attach_printable("Wrong size of token state"));
+        }
+
+        let owner: [u8; 33] = data[..33].try_into().unwrap();
+        let flags = data[33];
+
+        Ok(TokenState {
+            owner,
+            assigned: flags & ASSIGNED_FLAG != 0,
+            burned: flags & BURNED_FLAG != 0,
+        })
+    }
+}
+
+/// State of the newly issued token
+///
+/// token hash can't be taken by another token
+pub fn issue_token(state: Option<&TokenState>, token: &Token) -> Result<TokenState, TokenError> {
+    if state.is_some() {
+        return Err(action_error("token already exists"));
+    }
+
+    Ok(TokenState::new(
+        *token.get_current_owner(),
+        token.is_assigned(),
+        false,
+    ))
+}
+
+/// State of the token after the action
+///
+/// only the current owner can act on the token,
+/// only unassigned tokens can be assigned, burned tokens can't be changed
+pub fn apply_action(
+    state: Option<&TokenState>,
+    action: &TokenAction,
+) -> Result<TokenState, TokenError> {
+    let state = state.ok_or_else(|| action_error("unknown token"))?;
+
+    if state.burned {
+        return Err(action_error("token is burned"));
+    }
+
+    if state.owner != *action.get_previous_owner() {
+        return Err(action_error("only the owner can act on the token"));
+    }
+
+    let mut new_state = state.clone();
+    match action.get_action() {
+        Action::Send => {
+            new_state.owner = *action.get_current_owner();
+        }
+        Action::Assign => {
+            if state.assigned {
+                return Err(action_error("token is already assigned"));
+            }
+            new_state.assigned = true;
+            new_state.owner = *action.get_current_owner();
+        }
+        Action::Burn => {
+            new_state.burned = true;
+        }
+    }
+
+    Ok(new_state)
+}
+
+/// Store of token states, updated with blocks
+#[derive(Debug)]
+pub struct TokenLedger {
+    tokens: sled::Db,
+    owners: sled::Tree,
+}
+
+impl TokenLedger {
+    pub fn open(path: &Path) -> Result<TokenLedger, TokenError> {
+        let tokens = sled::open(path)
+            .report()
+            .change_context(TokenError::Token(TokenErrorKind::Ledger))
+            .attach_printable(format!("failed to open token ledger at {}", path.display()))?;
+
+        let owners = tokens
+            .open_tree(OWNERS_TREE)
+            .report()
+            .change_context(TokenError::Token(TokenErrorKind::Ledger))?;
+
+        Ok(TokenLedger { tokens, owners })
+    }
+
+    pub fn get(&self, token_hash: &[u8; 32]) -> Result<Option<TokenState>, TokenError> {
+        let dump = self
+            .tokens
+            .get(token_hash)
+            .report()
+            .change_context(TokenError::Token(TokenErrorKind::Ledger))?;
+
+        match dump {
+            None => Ok(None),
+            Some(dump) => TokenState::parse(&dump).map(Some),
+        }
+    }
+
+    /// Hashes of the tokens owned by the address, burned tokens aren't included
+    pub fn get_by_owner(&self, owner: &[u8; 33]) -> Result<Vec<[u8; 32]>, TokenError> {
+        let mut tokens: Vec<[u8; 32]> = Vec::new();
+
+        for key in self.owners.scan_prefix(owner).keys() {
+            let key = key
+                .report()
+                .change_context(TokenError::Token(TokenErrorKind::Ledger))?;
+            tokens.push(key[33..].try_into().unwrap());
+        }
+
+        Ok(tokens)
+    }
+
+    /// Calculates states of the tokens changed by the transactions
+    ///
+    /// transactions are applied in order, so later ones see the changes of earlier ones
+    pub fn calculate_changes(
+        &self,
+        transactions: &[Box<dyn Transactionable>],
+    ) -> Result<TokenChanges, TokenError> {
+        let mut changes: TokenChanges = HashMap::new();
+
+        for transaction in transactions.iter() {
+            let token_hash = match (transaction.get_token(), transaction.get_token_action()) {
+                (Some(token), _) => *token.get_token_hash(),
+                (None, Some(action)) => *action.get_token_hash(),
+                (None, None) => continue,
+            };
+
+            let current = match changes.get(&token_hash) {
+                Some((_, current)) => Some(current.clone()),
+                None => self.get(&token_hash)?,
+            };
+
+            let new = match transaction.get_token() {
+                Some(token) => issue_token(current.as_ref(), token)?,
+                None => apply_action(current.as_ref(), transaction.get_token_action().unwrap())?,
+            };
+
+            match changes.get_mut(&token_hash) {
+                Some((_, state)) => *state = new,
+                None => {
+                    changes.insert(token_hash, (current, new));
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Writes calculated changes and updates the owners tree
+    pub async fn apply(&self, changes: &TokenChanges) -> Result<(), TokenError> {
+        let mut tokens = sled::Batch::default();
+        let mut owners = sled::Batch::default();
+
+        for (token_hash, (previous, new)) in changes.iter() {
+            if let Some(previous) = previous {
+                if !previous.burned {
+                    owners.remove(&owner_key(&previous.owner, token_hash) as &[u8]);
+                }
+            }
+            if !new.burned {
+                owners.insert(&owner_key(&new.owner, token_hash) as &[u8], &[]);
+            }
+
+            tokens.insert(token_hash as &[u8], new.dump());
+        }
+
+        self.tokens
+            .apply_batch(tokens)
+            .report()
+            .change_context(TokenError::Token(TokenErrorKind::Ledger))?;
+
+        self.owners
+            .apply_batch(owners)
+            .report()
+            .change_context(TokenError::Token(TokenErrorKind::Ledger))?;
+
+        self.tokens
+            .flush_async()
+            .await
+            .report()
+            .change_context(TokenError::Token(TokenErrorKind::Ledger))?;
+
+        Ok(())
+    }
+}
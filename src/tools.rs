@@ -2,7 +2,6 @@ use crate::errors::*;
 use error_stack::{IntoReport, Report, Result, ResultExt};
 use num_bigint::BigUint;
 use sha2::{Digest, Sha256};
-use std::convert::TryInto;
 use std::fs::File;
 use std::io::Read;
 use std::io::Write;
@@ -28,7 +27,7 @@ pub fn dump_biguint(number: &BigUint, buffer: &mut Vec<u8>) -> Result<(), ToolsE
 pub fn load_biguint(data: &[u8]) -> Result<(BigUint, usize), ToolsError> {
     let amount_of_bunches: u8 = data[0];
     let amount_of_bytes: usize = amount_of_bunches as usize; //*4;
-    if data.len() < amount_of_bytes {
+    if data.len() < 1 + amount_of_bytes {
         return Err(
             Report::new(ToolsError::Biguint(BiguintErrorKind::Load)).attach_printable(format!(
                 "data = {} // bytes = {}",
@@ -44,9 +43,14 @@ pub fn load_biguint(data: &[u8]) -> Result<(BigUint, usize), ToolsError> {
 }
 
 pub fn bigint_size(number: &BigUint) -> usize {
+    // zero is dumped as a single zero byte
+    if number.bits() == 0 {
+        return 2;
+    }
+
     let bits_size: usize = number.bits() as usize;
     let mut amount_byte_size: usize = bits_size / 8;
-    if number.bits() % 8 != 0 {
+    if !number.bits().is_multiple_of(8) {
         amount_byte_size += 1;
     }
 
@@ -56,7 +60,7 @@ pub fn bigint_size(number: &BigUint) -> usize {
 pub fn hash(data: &[u8]) -> [u8; 32] {
     let mut hasher = Sha256::new();
     hasher.update(data);
-    hasher.finalize().as_slice().try_into().unwrap()
+    hasher.finalize().into()
 }
 
 pub fn compress_to_file(output_file: String, data: &[u8]) -> Result<(), ToolsError> {
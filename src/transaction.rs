@@ -1,22 +1,134 @@
 use crate::errors::*;
+use crate::multisig::{MultisigRegistration, MultisigTransaction};
+use crate::token::{Token, TokenAction};
 use crate::tools;
 use num_bigint::BigUint;
+use num_traits::Zero;
 use sha2::{Digest, Sha256};
 use std::convert::TryInto;
 use std::fmt::Debug;
 
 use crate::dump_headers::Headers;
 use secp256k1::ecdsa::Signature;
+use secp256k1::schnorr;
 use secp256k1::PublicKey;
-use secp256k1::{Message, Secp256k1, SecretKey};
+use secp256k1::{KeyPair, Message, Secp256k1, SecretKey, VerifyOnly, XOnlyPublicKey};
 
 use error_stack::{IntoReport, Report, Result, ResultExt};
 
-pub trait Transactionable: Debug {
+/*
+    Versioned transaction dumping protocol
+
+    Header (Headers::VersionedTransaction) - 1 byte
+    Version - 1 byte
+    Sender - 33 bytes
+    Receiver - 33 bytes
+    Timestamp - 8 bytes
+    Signature - 64 bytes
+    Nonce - 8 bytes
+    Amount - biguint
+    Fee - biguint (since FEE_VERSION)
+    Lock time - 8 bytes (since LOCK_VERSION)
+    Signature scheme - 1 byte (since SCHEME_VERSION)
+    Expiry height - 8 bytes (since EXPIRY_VERSION)
+
+    Unlike legacy transactions versioned ones are not signed over
+    the previous block hash, replays are rejected by the sender's nonce.
+    Legacy transactions aren't accepted from NONCE_ACTIVATION_HEIGHT.
+
+    Lock time below LOCK_TIME_THRESHOLD is the lowest height of the block
+    the transaction can be included in, otherwise it's the lowest timestamp.
+
+    Transactions before SCHEME_VERSION are signed with ECDSA, later ones
+    are signed with the scheme they are tagged with. Schnorr signatures
+    are checked against the x coordinate of the sender's key.
+
+    Transaction can't be included in blocks higher than its expiry height,
+    zero expiry height means the transaction never expires.
+*/
+
+/// version of transactions dumped with `Headers::Transaction`
+pub static LEGACY_VERSION: u8 = 0;
+/// version of transactions carrying a sender's nonce
+pub static NONCE_VERSION: u8 = 1;
+/// version of transactions paying a fee
+pub static FEE_VERSION: u8 = 2;
+/// version of transactions that can't be included before the lock time
+pub static LOCK_VERSION: u8 = 3;
+/// version of transactions tagged with the signature scheme
+pub static SCHEME_VERSION: u8 = 4;
+/// version of transactions valid until the expiry height
+pub static EXPIRY_VERSION: u8 = 5;
+/// version new versioned transactions are created with
+pub static CURRENT_VERSION: u8 = EXPIRY_VERSION;
+/// highest known version
+pub static LATEST_VERSION: u8 = EXPIRY_VERSION;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureScheme {
+    /// compact secp256k1 ECDSA signature
+    Ecdsa = 0,
+    /// BIP340 Schnorr signature
+    Schnorr = 1,
+}
+
+impl SignatureScheme {
+    pub fn from_u8(scheme: u8) -> Option<SignatureScheme> {
+        match scheme {
+            0 => Some(SignatureScheme::Ecdsa),
+            1 => Some(SignatureScheme::Schnorr),
+            _ => None,
+        }
+    }
+}
+
+/// lock times starting from this one are timestamps, lower ones are heights
+pub static LOCK_TIME_THRESHOLD: u64 = 500_000_000;
+
+/// height of the first block that doesn't accept transactions without a nonce
+pub static NONCE_ACTIVATION_HEIGHT: u64 = 1000;
+
+/// Whether transaction has to carry a nonce to be included
+/// in the block with the given height
+pub fn is_nonce_required(height: u64) -> bool {
+    height >= NONCE_ACTIVATION_HEIGHT
+}
+
+/// Whether transaction valid until the expiry height can't be included
+/// in the block with the given height
+pub fn is_expired(expiry_height: u64, height: u64) -> bool {
+    height > expiry_height
+}
+
+/// Whether transaction with the lock time can be included in the block
+/// with the given height and timestamp
+pub fn is_unlocked(lock_time: u64, height: u64, timestamp: u64) -> bool {
+    if lock_time < LOCK_TIME_THRESHOLD {
+        height >= lock_time
+    } else {
+        timestamp >= lock_time
+    }
+}
+
+pub trait Transactionable: Debug + Send + Sync {
+    /// hash binding the transaction to the block it's included after
     fn hash(&self, prev_hash: &[u8; 32]) -> [u8; 32];
     fn hash_without_signature(&self, prev_hash: &[u8; 32]) -> Box<[u8; 32]>;
+    /// stable id of the transaction, hash of the signed data without the previous block hash
+    ///
+    /// transactions are stored under it in the pool and in the indexes,
+    /// it's also a leaf of the block's merkle tree
+    fn get_id(&self) -> [u8; 32];
 
-    fn verify(&self, prev_hash: &[u8; 32]) -> Result<bool, TransactionError>;
+    fn verify(&self, prev_hash: &[u8; 32]) -> Result<bool, TransactionError> {
+        self.verify_with_context(prev_hash, &Secp256k1::verification_only())
+    }
+    /// verifies using already created secp256k1 context
+    fn verify_with_context(
+        &self,
+        prev_hash: &[u8; 32],
+        verifier: &Secp256k1<VerifyOnly>,
+    ) -> Result<bool, TransactionError>;
 
     fn dump(&self) -> Result<Vec<u8>, TransactionError>;
     fn get_dump_size(&self) -> usize;
@@ -29,20 +141,61 @@ pub trait Transactionable: Debug {
     fn get_receiver(&self) -> &[u8; 33];
     fn get_timestamp(&self) -> u64;
     fn get_signature(&self) -> &[u8; 64];
+    /// amount of coins moved from sender to receiver, if any
+    fn get_amount(&self) -> Option<&BigUint>;
+    /// sender's nonce, transactions without it can't be protected from replays
+    fn get_nonce(&self) -> Option<u64>;
+    /// fee paid by the sender on top of the amount
+    fn get_fee(&self) -> Option<&BigUint>;
+    /// height or timestamp before which transaction can't be included in a block
+    fn get_lock_time(&self) -> Option<u64>;
+    /// arbitrary data anchored on chain by the transaction
+    fn get_payload(&self) -> Option<&[u8]>;
+    /// height of the last block transaction can be included in
+    fn get_expiry_height(&self) -> Option<u64>;
+    /// token issued by the entry
+    fn get_token(&self) -> Option<&Token> {
+        None
+    }
+    /// action changing the owner or the state of a token
+    fn get_token_action(&self) -> Option<&TokenAction> {
+        None
+    }
+    /// spend from a multisig account, authorized by its registered key set
+    fn get_multisig(&self) -> Option<&MultisigTransaction> {
+        None
+    }
+    /// registration of the key set of a multisig account
+    fn get_multisig_registration(&self) -> Option<&MultisigRegistration> {
+        None
+    }
     fn sign(
         &mut self,
         prev_hash: &[u8; 32],
         private_key: &[u8; 32],
     ) -> Result<(), TransactionError>;
+    fn clone_box(&self) -> Box<dyn Transactionable>;
+}
+
+impl Clone for Box<dyn Transactionable> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Transaction {
+    version: u8,
     sender: [u8; 33],
     receiver: [u8; 33],
     timestamp: u64,
     signature: [u8; 64],
     amount: BigUint,
+    nonce: u64,
+    fee: BigUint,
+    lock_time: u64,
+    scheme: SignatureScheme,
+    expiry_height: u64,
 }
 
 impl Transaction {
@@ -54,53 +207,267 @@ impl Transaction {
         amount: BigUint,
     ) -> Transaction {
         Transaction {
+            version: LEGACY_VERSION,
             sender: *sender,
             receiver: *receiver,
             timestamp,
             signature: *signature,
             amount,
+            nonce: 0,
+            fee: BigUint::zero(),
+            lock_time: 0,
+            scheme: SignatureScheme::Ecdsa,
+            expiry_height: 0,
         }
     }
 
-    pub fn get_amount(&self) -> &BigUint {
-        &self.amount
+    pub fn new_versioned(
+        sender: &[u8; 33],
+        receiver: &[u8; 33],
+        timestamp: u64,
+        signature: &[u8; 64],
+        amount: BigUint,
+        fee: BigUint,
+        nonce: u64,
+    ) -> Transaction {
+        Transaction {
+            version: CURRENT_VERSION,
+            sender: *sender,
+            receiver: *receiver,
+            timestamp,
+            signature: *signature,
+            amount,
+            nonce,
+            fee,
+            lock_time: 0,
+            scheme: SignatureScheme::Ecdsa,
+            expiry_height: 0,
+        }
     }
-}
 
-impl Transactionable for Transaction {
-    fn hash(&self, prev_hash: &[u8; 32]) -> [u8; 32] {
-        let mut hasher = Sha256::new();
+    /// Sets the lock time, see `is_unlocked`
+    pub fn with_lock_time(mut self, lock_time: u64) -> Transaction {
+        self.lock_time = lock_time;
+        self
+    }
+
+    /// Sets the scheme the transaction is signed with
+    pub fn with_scheme(mut self, scheme: SignatureScheme) -> Transaction {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Sets the height of the last block transaction can be included in,
+    /// zero means the transaction never expires
+    pub fn with_expiry_height(mut self, expiry_height: u64) -> Transaction {
+        self.expiry_height = expiry_height;
+        self
+    }
+
+    pub fn get_signature_scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+
+    pub fn get_version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        self.version == LEGACY_VERSION
+    }
 
+    /// data covered by the signature
+    fn signed_data(&self, prev_hash: &[u8; 32]) -> Vec<u8> {
         let amount_as_bytes = self.amount.to_bytes_be();
-        let calculated_size: usize = 32 + 33 + 33 + 8 + amount_as_bytes.len();
 
-        let mut concatenated_input: Vec<u8> = Vec::with_capacity(calculated_size);
-        for byte in prev_hash.iter() {
-            concatenated_input.push(*byte);
+        if self.is_legacy() {
+            let calculated_size: usize = 32 + 33 + 33 + 8 + amount_as_bytes.len();
+
+            let mut concatenated_input: Vec<u8> = Vec::with_capacity(calculated_size);
+            concatenated_input.extend(prev_hash.iter());
+            concatenated_input.extend(self.sender.iter());
+            concatenated_input.extend(self.receiver.iter());
+            concatenated_input.extend(self.timestamp.to_be_bytes().iter());
+            concatenated_input.extend(amount_as_bytes.iter());
+
+            return concatenated_input;
         }
-        for byte in self.sender.iter() {
-            concatenated_input.push(*byte);
+
+        let fee_as_bytes = self.fee.to_bytes_be();
+        let calculated_size: usize =
+            1 + 33 + 33 + 8 + 8 + 1 + amount_as_bytes.len() + fee_as_bytes.len();
+
+        let mut concatenated_input: Vec<u8> = Vec::with_capacity(calculated_size);
+        concatenated_input.push(self.version);
+        concatenated_input.extend(self.sender.iter());
+        concatenated_input.extend(self.receiver.iter());
+        concatenated_input.extend(self.timestamp.to_be_bytes().iter());
+        concatenated_input.extend(self.nonce.to_be_bytes().iter());
+        if self.version >= FEE_VERSION {
+            // amount is prefixed with its length so it's bytes can't be moved into the fee
+            concatenated_input.push(amount_as_bytes.len() as u8);
+            concatenated_input.extend(amount_as_bytes.iter());
+            concatenated_input.extend(fee_as_bytes.iter());
+            if self.version >= LOCK_VERSION {
+                concatenated_input.extend(self.lock_time.to_be_bytes().iter());
+            }
+            if self.version >= SCHEME_VERSION {
+                concatenated_input.push(self.scheme as u8);
+            }
+            if self.version >= EXPIRY_VERSION {
+                concatenated_input.extend(self.expiry_height.to_be_bytes().iter());
+            }
+        } else {
+            concatenated_input.extend(amount_as_bytes.iter());
         }
-        for byte in self.receiver.iter() {
-            concatenated_input.push(*byte);
+
+        concatenated_input
+    }
+
+    /// Parses transaction dumped with `Headers::VersionedTransaction`
+    ///
+    /// data shouldn't contain the header
+    pub fn parse_versioned(data: &[u8], size: u64) -> Result<Transaction, TransactionError> {
+        if data.len() <= 147 {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Data length <= 147"));
         }
-        for byte in self.signature.iter() {
-            concatenated_input.push(*byte);
+
+        // parsing version
+        let version = data[0];
+        if version == LEGACY_VERSION || version > LATEST_VERSION {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable(format!("Unknown transaction version: {}", version)));
         }
-        for byte in self.timestamp.to_be_bytes().iter() {
-            concatenated_input.push(*byte);
+        let mut index: usize = 1;
+
+        let (sender, receiver, timestamp, signature) = Transaction::parse_common(data, &mut index);
+
+        // parsing nonce
+        let nonce: u64 = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
+        index += 8;
+
+        // parsing amount
+        if index >= data.len() {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Couldn't parse amount"));
         }
-        for byte in amount_as_bytes.iter() {
-            concatenated_input.push(*byte);
+        let (amount, idx) = tools::load_biguint(&data[index..])
+            .attach_printable("Couldn't parse amount")
+            .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+
+        index += idx;
+
+        // parsing fee
+        let fee = if version >= FEE_VERSION {
+            if index >= data.len() {
+                return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                    .attach_printable("Couldn't parse fee"));
+            }
+            let (fee, idx) = tools::load_biguint(&data[index..])
+                .attach_printable("Couldn't parse fee")
+                .change_context(TransactionError::Tx(TxErrorKind::Parse))?;
+            index += idx;
+            fee
+        } else {
+            BigUint::zero()
+        };
+
+        // parsing lock time
+        let lock_time = if version >= LOCK_VERSION {
+            if data.len() < index + 8 {
+                return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                    .attach_printable("Couldn't parse lock time"));
+            }
+            let lock_time = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
+            index += 8;
+            lock_time
+        } else {
+            0
+        };
+
+        // parsing signature scheme
+        let scheme = if version >= SCHEME_VERSION {
+            let scheme = data
+                .get(index)
+                .and_then(|scheme| SignatureScheme::from_u8(*scheme))
+                .ok_or_else(|| {
+                    Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                        .attach_printable("Couldn't parse signature scheme")
+                })?;
+            index += 1;
+            scheme
+        } else {
+            SignatureScheme::Ecdsa
+        };
+
+        // parsing expiry height
+        let expiry_height = if version >= EXPIRY_VERSION {
+            if data.len() < index + 8 {
+                return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                    .attach_printable("Couldn't parse expiry height"));
+            }
+            let expiry_height = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
+            index += 8;
+            expiry_height
+        } else {
+            0
+        };
+
+        if index != size as usize {
+            return Err(Report::new(TransactionError::Tx(TxErrorKind::Parse))
+                .attach_printable("Index != Tx size"));
         }
 
-        hasher.update(concatenated_input);
-        hasher.finalize().as_slice().try_into().unwrap()
+        Ok(Transaction {
+            version,
+            sender,
+            receiver,
+            timestamp,
+            signature,
+            amount,
+            nonce,
+            fee,
+            lock_time,
+            scheme,
+            expiry_height,
+        })
     }
 
-    fn hash_without_signature(&self, prev_hash: &[u8; 32]) -> Box<[u8; 32]> {
+    /// parses sender, receiver, timestamp and signature
+    fn parse_common(data: &[u8], index: &mut usize) -> ([u8; 33], [u8; 33], u64, [u8; 64]) {
+        // parsing sender address
+        let sender: [u8; 33] = unsafe { data[*index..*index + 33].try_into().unwrap_unchecked() };
+        *index += 33;
+
+        // parsing receiver address
+        let receiver: [u8; 33] = unsafe { data[*index..*index + 33].try_into().unwrap_unchecked() };
+        *index += 33;
+
+        // parsing timestamp
+        let timestamp: u64 = u64::from_be_bytes(data[*index..*index + 8].try_into().unwrap());
+        *index += 8;
+
+        // parsing signature
+        let signature: [u8; 64] =
+            unsafe { data[*index..*index + 64].try_into().unwrap_unchecked() };
+        *index += 64;
+
+        (sender, receiver, timestamp, signature)
+    }
+}
+
+impl Transactionable for Transaction {
+    fn hash(&self, prev_hash: &[u8; 32]) -> [u8; 32] {
         let mut hasher = Sha256::new();
 
+        if !self.is_legacy() {
+            hasher.update(prev_hash);
+            hasher.update(self.signed_data(prev_hash));
+            hasher.update(self.signature);
+            return hasher.finalize().into();
+        }
+
         let amount_as_bytes = self.amount.to_bytes_be();
         let calculated_size: usize = 32 + 33 + 33 + 8 + amount_as_bytes.len();
 
@@ -114,6 +481,9 @@ impl Transactionable for Transaction {
         for byte in self.receiver.iter() {
             concatenated_input.push(*byte);
         }
+        for byte in self.signature.iter() {
+            concatenated_input.push(*byte);
+        }
         for byte in self.timestamp.to_be_bytes().iter() {
             concatenated_input.push(*byte);
         }
@@ -122,12 +492,34 @@ impl Transactionable for Transaction {
         }
 
         hasher.update(concatenated_input);
-        let result: [u8; 32] = hasher.finalize().as_slice().try_into().unwrap();
+        hasher.finalize().into()
+    }
+
+    fn hash_without_signature(&self, prev_hash: &[u8; 32]) -> Box<[u8; 32]> {
+        let mut hasher = Sha256::new();
+
+        hasher.update(self.signed_data(prev_hash));
+        let result: [u8; 32] = hasher.finalize().into();
 
         Box::new(result)
     }
 
-    fn verify(&self, prev_hash: &[u8; 32]) -> Result<bool, TransactionError> {
+    fn get_id(&self) -> [u8; 32] {
+        let signed_data = self.signed_data(&[0u8; 32]);
+
+        // legacy transactions are signed over the previous block hash
+        if self.is_legacy() {
+            return tools::hash(&signed_data[32..]);
+        }
+
+        tools::hash(&signed_data)
+    }
+
+    fn verify_with_context(
+        &self,
+        prev_hash: &[u8; 32],
+        verifier: &Secp256k1<VerifyOnly>,
+    ) -> Result<bool, TransactionError> {
         let signed_data_hash: Box<[u8; 32]> = self.hash_without_signature(prev_hash);
 
         // load sender
@@ -135,21 +527,28 @@ impl Transactionable for Transaction {
             .report()
             .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
 
-        // creating verifier
-        let verifier = Secp256k1::verification_only();
-
         // load message
-        let message = Message::from_slice(Box::leak(signed_data_hash))
-            .report()
-            .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
-
-        // load signature
-        let signature = Signature::from_compact(&self.signature)
+        let message = Message::from_slice(signed_data_hash.as_ref())
             .report()
             .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
 
         // verifying hashed data with public key
-        let result = verifier.verify_ecdsa(&message, &signature, &sender);
+        let result = match self.scheme {
+            SignatureScheme::Ecdsa => {
+                let signature = Signature::from_compact(&self.signature)
+                    .report()
+                    .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+                verifier.verify_ecdsa(&message, &signature, &sender)
+            }
+            SignatureScheme::Schnorr => {
+                let signature = schnorr::Signature::from_slice(&self.signature)
+                    .report()
+                    .change_context(TransactionError::Tx(TxErrorKind::Verify))?;
+
+                verifier.verify_schnorr(&signature, &message, &XOnlyPublicKey::from(sender))
+            }
+        };
 
         match result {
             Err(_) => Ok(false),
@@ -165,7 +564,12 @@ impl Transactionable for Transaction {
         let mut transaction_dump: Vec<u8> = Vec::with_capacity(calculated_size);
 
         // header
-        transaction_dump.push(Headers::Transaction as u8);
+        if self.is_legacy() {
+            transaction_dump.push(Headers::Transaction as u8);
+        } else {
+            transaction_dump.push(Headers::VersionedTransaction as u8);
+            transaction_dump.push(self.version);
+        }
 
         // sender
         for byte in self.sender.iter() {
@@ -185,15 +589,61 @@ impl Transactionable for Transaction {
             transaction_dump.push(*byte);
         }
 
+        // nonce
+        if !self.is_legacy() {
+            transaction_dump.extend(self.nonce.to_be_bytes().iter());
+        }
+
         // amount
         tools::dump_biguint(&self.amount, &mut transaction_dump)
             .change_context(TransactionError::Tx(TxErrorKind::Dump))?;
 
+        // fee
+        if self.version >= FEE_VERSION {
+            tools::dump_biguint(&self.fee, &mut transaction_dump)
+                .change_context(TransactionError::Tx(TxErrorKind::Dump))?;
+        }
+
+        // lock time
+        if self.version >= LOCK_VERSION {
+            transaction_dump.extend(self.lock_time.to_be_bytes().iter());
+        }
+
+        // signature scheme
+        if self.version >= SCHEME_VERSION {
+            transaction_dump.push(self.scheme as u8);
+        }
+
+        // expiry height
+        if self.version >= EXPIRY_VERSION {
+            transaction_dump.extend(self.expiry_height.to_be_bytes().iter());
+        }
+
         Ok(transaction_dump)
     }
 
     fn get_dump_size(&self) -> usize {
-        1 + 33 + 33 + 8 + 64 + tools::bigint_size(&self.amount)
+        // header, sender, receiver, timestamp, signature and amount
+        let mut size = 1 + 33 + 33 + 8 + 64 + tools::bigint_size(&self.amount);
+
+        if !self.is_legacy() {
+            // version and nonce
+            size += 1 + 8;
+        }
+        if let Some(fee) = self.get_fee() {
+            size += tools::bigint_size(fee);
+        }
+        if self.get_lock_time().is_some() {
+            size += 8;
+        }
+        if self.version >= SCHEME_VERSION {
+            size += 1;
+        }
+        if self.version >= EXPIRY_VERSION {
+            size += 8;
+        }
+
+        size
     }
 
     fn parse(data: &[u8], size: u64) -> Result<Transaction, TransactionError> {
@@ -204,21 +654,7 @@ impl Transactionable for Transaction {
                 .attach_printable("Data length <= 138"));
         }
 
-        // parsing sender address
-        let sender: [u8; 33] = unsafe { data[index..index + 33].try_into().unwrap_unchecked() };
-        index += 33;
-
-        // parsing receiver address
-        let receiver: [u8; 33] = unsafe { data[index..index + 33].try_into().unwrap_unchecked() };
-        index += 33;
-
-        // parsing timestamp
-        let timestamp: u64 = u64::from_be_bytes(data[index..index + 8].try_into().unwrap());
-        index += 8;
-
-        // parsing signature
-        let signature: [u8; 64] = unsafe { data[index..index + 64].try_into().unwrap_unchecked() };
-        index += 64;
+        let (sender, receiver, timestamp, signature) = Transaction::parse_common(data, &mut index);
 
         // parsing amount
         let (amount, idx) = tools::load_biguint(&data[index..])
@@ -252,45 +688,69 @@ impl Transactionable for Transaction {
         &self.signature
     }
 
-    fn sign(
-        &mut self,
-        prev_hash: &[u8; 32],
-        private_key: &[u8; 32],
-    ) -> Result<(), TransactionError> {
-        let mut hasher = Sha256::new();
-
-        let amount_as_bytes = self.amount.to_bytes_be();
-        let calculated_size: usize = 32 + 33 + 33 + 8 + amount_as_bytes.len();
+    fn get_amount(&self) -> Option<&BigUint> {
+        Some(&self.amount)
+    }
 
-        let mut concatenated_input: Vec<u8> = Vec::with_capacity(calculated_size);
-        for byte in prev_hash.iter() {
-            concatenated_input.push(*byte);
-        }
-        for byte in self.sender.iter() {
-            concatenated_input.push(*byte);
+    fn get_nonce(&self) -> Option<u64> {
+        if self.is_legacy() {
+            return None;
         }
-        for byte in self.receiver.iter() {
-            concatenated_input.push(*byte);
+        Some(self.nonce)
+    }
+
+    fn get_fee(&self) -> Option<&BigUint> {
+        if self.version < FEE_VERSION {
+            return None;
         }
-        for byte in self.timestamp.to_be_bytes().iter() {
-            concatenated_input.push(*byte);
+        Some(&self.fee)
+    }
+
+    fn get_lock_time(&self) -> Option<u64> {
+        if self.version < LOCK_VERSION {
+            return None;
         }
-        for byte in amount_as_bytes.iter() {
-            concatenated_input.push(*byte);
+
+        Some(self.lock_time)
+    }
+
+    fn get_payload(&self) -> Option<&[u8]> {
+        None
+    }
+
+    fn get_expiry_height(&self) -> Option<u64> {
+        if self.version < EXPIRY_VERSION || self.expiry_height == 0 {
+            return None;
         }
 
-        hasher.update(concatenated_input);
-        let result: [u8; 32] = hasher.finalize().as_slice().try_into().unwrap();
-        let message = unsafe { Message::from_slice(&result).unwrap_unchecked() };
+        Some(self.expiry_height)
+    }
+
+    fn sign(
+        &mut self,
+        prev_hash: &[u8; 32],
+        private_key: &[u8; 32],
+    ) -> Result<(), TransactionError> {
+        let result: Box<[u8; 32]> = self.hash_without_signature(prev_hash);
+        let message = unsafe { Message::from_slice(result.as_ref()).unwrap_unchecked() };
 
         let secret_key = unsafe { SecretKey::from_slice(private_key).unwrap_unchecked() };
 
         let signer = Secp256k1::new();
 
-        let signature = signer.sign_ecdsa(&message, &secret_key);
-
-        self.signature = signature.serialize_compact();
+        self.signature = match self.scheme {
+            SignatureScheme::Ecdsa => signer.sign_ecdsa(&message, &secret_key).serialize_compact(),
+            SignatureScheme::Schnorr => {
+                let keypair = KeyPair::from_secret_key(&signer, secret_key);
+                let mut signature = [0u8; 64];
+                signature.copy_from_slice(signer.sign_schnorr(&message, &keypair).as_ref());
+                signature
+            }
+        };
 
         Ok(())
     }
+    fn clone_box(&self) -> Box<dyn Transactionable> {
+        Box::new(self.clone())
+    }
 }
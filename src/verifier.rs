@@ -0,0 +1,150 @@
+use crate::errors::*;
+use crate::transaction::Transactionable;
+use secp256k1::{Secp256k1, VerifyOnly};
+use std::collections::{HashSet, VecDeque};
+use std::thread;
+
+use error_stack::{Report, Result};
+
+/// Amount of verified transactions remembered by default
+pub static DEFAULT_CACHE_SIZE: usize = 100_000;
+
+/// Batches smaller than this are verified on the calling thread
+static MIN_PARALLEL_BATCH: usize = 64;
+
+/// Verifies signatures of transactions with one shared secp256k1 context
+///
+/// remembers hashes of verified transactions, so transactions checked
+/// on admission to the pool aren't checked again on block import
+#[derive(Debug)]
+pub struct BatchVerifier {
+    context: Secp256k1<VerifyOnly>,
+    threads: usize,
+    cache_size: usize,
+    verified: HashSet<[u8; 32]>,
+    verified_order: VecDeque<[u8; 32]>,
+}
+
+impl Default for BatchVerifier {
+    fn default() -> Self {
+        let threads = thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(1);
+
+        Self::new(threads, DEFAULT_CACHE_SIZE)
+    }
+}
+
+impl BatchVerifier {
+    pub fn new(threads: usize, cache_size: usize) -> BatchVerifier {
+        BatchVerifier {
+            context: Secp256k1::verification_only(),
+            threads: threads.max(1),
+            cache_size,
+            verified: HashSet::new(),
+            verified_order: VecDeque::new(),
+        }
+    }
+
+    /// Whether transaction with the hash was already verified
+    pub fn is_verified(&self, hash: &[u8; 32]) -> bool {
+        self.verified.contains(hash)
+    }
+
+    fn remember(&mut self, hash: [u8; 32]) {
+        if self.cache_size == 0 || !self.verified.insert(hash) {
+            return;
+        }
+        self.verified_order.push_back(hash);
+
+        while self.verified_order.len() > self.cache_size {
+            if let Some(oldest) = self.verified_order.pop_front() {
+                self.verified.remove(&oldest);
+            }
+        }
+    }
+
+    /// Verifies a single transaction
+    pub fn verify(
+        &mut self,
+        transaction: &dyn Transactionable,
+        prev_hash: &[u8; 32],
+    ) -> Result<bool, TransactionError> {
+        let hash = transaction.hash(prev_hash);
+        if self.is_verified(&hash) {
+            return Ok(true);
+        }
+
+        if !transaction.verify_with_context(prev_hash, &self.context)? {
+            return Ok(false);
+        }
+
+        self.remember(hash);
+        Ok(true)
+    }
+
+    /// Verifies all the transactions, splitting them between threads
+    ///
+    /// returns false if any of the signatures is wrong
+    pub fn verify_batch(
+        &mut self,
+        transactions: &[Box<dyn Transactionable>],
+        prev_hash: &[u8; 32],
+    ) -> Result<bool, TransactionError> {
+        let pending: Vec<(&dyn Transactionable, [u8; 32])> = transactions
+            .iter()
+            .map(|transaction| (transaction.as_ref(), transaction.hash(prev_hash)))
+            .filter(|(_, hash)| !self.is_verified(hash))
+            .collect();
+
+        if pending.is_empty() {
+            return Ok(true);
+        }
+
+        let threads = if pending.len() < MIN_PARALLEL_BATCH {
+            1
+        } else {
+            self.threads
+        };
+        let chunk_size = pending.len().div_ceil(threads);
+
+        let context = &self.context;
+        let results: Vec<Result<bool, TransactionError>> = thread::scope(|scope| {
+            let handles: Vec<_> = pending
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        for (transaction, _) in chunk.iter() {
+                            if !transaction.verify_with_context(prev_hash, context)? {
+                                return Ok(false);
+                            }
+                        }
+                        Ok(true)
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle.join().unwrap_or_else(|_| {
+                        Err(Report::new(TransactionError::Tx(TxErrorKind::Verify))
+                            .attach_printable("verifying thread panicked"))
+                    })
+                })
+                .collect()
+        });
+
+        for result in results {
+            if !result? {
+                return Ok(false);
+            }
+        }
+
+        for (_, hash) in pending {
+            self.remember(hash);
+        }
+
+        Ok(true)
+    }
+}
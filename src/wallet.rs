@@ -0,0 +1,297 @@
+use crate::errors::*;
+use crate::hd::{address_path, ExtendedKey, GAP_LIMIT};
+use crate::transaction::Transactionable;
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::Aes256Gcm;
+use bip39::Mnemonic;
+use hmac::Hmac;
+use secp256k1::rand::{thread_rng, RngCore};
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+use error_stack::{Context, IntoReport, Report, Result, ResultExt};
+
+// Keystore file layout:
+// [version: u8][pbkdf2 rounds: u32][salt: 16 bytes][nonce: 12 bytes][ciphertext]
+//
+// encryption key is pbkdf2-hmac-sha256 of the password,
+// plaintext is the concatenation of 32 byte secret keys
+
+static KEYSTORE_VERSION: u8 = 1;
+pub static PBKDF2_ROUNDS: u32 = 100_000;
+static SALT_SIZE: usize = 16;
+static NONCE_SIZE: usize = 12;
+static KEYSTORE_HEADER_SIZE: usize = 1 + 4 + 16 + 12;
+static MNEMONIC_WORDS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// Derives compressed public key used as an address in transactions
+pub fn address_from_secret_key(secret_key: &[u8; 32]) -> Result<[u8; 33], WalletError> {
+    let secret_key = SecretKey::from_slice(secret_key)
+        .report()
+        .change_context(WalletError::Wallet(WalletErrorKind::InvalidKey))?;
+
+    Ok(PublicKey::from_secret_key(&Secp256k1::signing_only(), &secret_key).serialize())
+}
+
+/// Generates new secret key
+pub fn generate_secret_key() -> [u8; 32] {
+    let secret_key = SecretKey::new(&mut thread_rng());
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&secret_key[..]);
+    bytes
+}
+
+/// Generates BIP39 mnemonic phrase of 12, 15, 18, 21 or 24 words
+pub fn generate_mnemonic(words: usize) -> Result<String, WalletError> {
+    if !MNEMONIC_WORDS.contains(&words) {
+        return Err(Report::new(WalletError::Wallet(WalletErrorKind::Mnemonic))
+            .attach_printable(format!("unsupported amount of words: {}", words)));
+    }
+
+    let mut entropy = [0u8; 32];
+    thread_rng().fill_bytes(&mut entropy);
+
+    let mnemonic = Mnemonic::from_entropy(&entropy[..words * 4 / 3]).map_err(|error| {
+        Report::new(WalletError::Wallet(WalletErrorKind::Mnemonic))
+            .attach_printable(error.to_string())
+    })?;
+
+    Ok(mnemonic.to_string())
+}
+
+/// Checks the mnemonic phrase and derives BIP39 seed from it
+pub fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> Result<[u8; 64], WalletError> {
+    let mnemonic = Mnemonic::parse(phrase).map_err(|error| {
+        Report::new(WalletError::Wallet(WalletErrorKind::Mnemonic))
+            .attach_printable(error.to_string())
+    })?;
+
+    Ok(mnemonic.to_seed(passphrase))
+}
+
+fn derive_key(password: &[u8], salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password, salt, rounds, &mut key);
+    key
+}
+
+#[derive(Debug, Default)]
+pub struct Wallet {
+    keys: HashMap<[u8; 33], [u8; 32]>,
+}
+
+impl Wallet {
+    pub fn new() -> Wallet {
+        Wallet::default()
+    }
+
+    /// Generates new keypair and returns its address
+    pub fn generate_key(&mut self) -> [u8; 33] {
+        loop {
+            let secret_key = generate_secret_key();
+            if let Ok(address) = self.import_key(&secret_key) {
+                return address;
+            }
+        }
+    }
+
+    /// Adds existing secret key and returns its address
+    pub fn import_key(&mut self, secret_key: &[u8; 32]) -> Result<[u8; 33], WalletError> {
+        let address = address_from_secret_key(secret_key)?;
+        self.keys.insert(address, *secret_key);
+        Ok(address)
+    }
+
+    pub fn contains(&self, address: &[u8; 33]) -> bool {
+        self.keys.contains_key(address)
+    }
+
+    pub fn get_secret_key(&self, address: &[u8; 33]) -> Option<&[u8; 32]> {
+        self.keys.get(address)
+    }
+
+    pub fn get_addresses(&self) -> Vec<[u8; 33]> {
+        self.keys.keys().copied().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Imports `count` keys of the seed starting from the address index `from`
+    pub fn derive_keys(
+        &mut self,
+        seed: &[u8],
+        from: u32,
+        count: u32,
+    ) -> Result<Vec<[u8; 33]>, WalletError> {
+        let master = ExtendedKey::from_seed(seed)?;
+
+        let mut addresses: Vec<[u8; 33]> = Vec::with_capacity(count as usize);
+        for index in from..from + count {
+            let key = master.derive_path(&address_path(index))?;
+            addresses.push(self.import_key(key.get_secret_key())?);
+        }
+
+        Ok(addresses)
+    }
+
+    /// Restores keys of the seed phrase that were ever used
+    ///
+    /// derives addresses one by one until `GAP_LIMIT` addresses in a row aren't used
+    pub fn recover<E: Context, F: FnMut(&[u8; 33]) -> Result<bool, E>>(
+        phrase: &str,
+        passphrase: &str,
+        mut is_used: F,
+    ) -> Result<Wallet, WalletError> {
+        let seed = mnemonic_to_seed(phrase, passphrase)?;
+        let master = ExtendedKey::from_seed(&seed)?;
+
+        let mut wallet = Wallet::new();
+        let mut unused: u32 = 0;
+        let mut index: u32 = 0;
+        while unused < GAP_LIMIT {
+            let key = master.derive_path(&address_path(index))?;
+            let address = key.get_address()?;
+
+            if is_used(&address).change_context(WalletError::Wallet(WalletErrorKind::Recover))? {
+                wallet.import_key(key.get_secret_key())?;
+                unused = 0;
+            } else {
+                unused += 1;
+            }
+            index += 1;
+        }
+
+        Ok(wallet)
+    }
+
+    /// Signs transaction with the key of its sender
+    pub fn sign<T: Transactionable + ?Sized>(
+        &self,
+        transaction: &mut T,
+        prev_hash: &[u8; 32],
+    ) -> Result<(), WalletError> {
+        let secret_key = self.keys.get(transaction.get_sender()).ok_or_else(|| {
+            Report::new(WalletError::Wallet(WalletErrorKind::UnknownAddress))
+                .attach_printable("no key for the sender of transaction")
+        })?;
+
+        transaction
+            .sign(prev_hash, secret_key)
+            .change_context(WalletError::Wallet(WalletErrorKind::Sign))
+    }
+
+    /// Encrypts keys with the password
+    pub fn dump(&self, password: &[u8]) -> Result<Vec<u8>, WalletError> {
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        thread_rng().fill_bytes(&mut salt);
+        thread_rng().fill_bytes(&mut nonce);
+
+        let key = derive_key(password, &salt, PBKDF2_ROUNDS);
+        let cipher = Aes256Gcm::new(&key.into());
+
+        let mut plaintext: Vec<u8> = Vec::with_capacity(self.keys.len() * 32);
+        for secret_key in self.keys.values() {
+            plaintext.extend(secret_key);
+        }
+
+        let ciphertext = cipher
+            .encrypt(&nonce.into(), plaintext.as_ref())
+            .map_err(|_| Report::new(WalletError::Wallet(WalletErrorKind::Encrypt)))?;
+
+        let mut dump: Vec<u8> = Vec::with_capacity(KEYSTORE_HEADER_SIZE + ciphertext.len());
+        dump.push(KEYSTORE_VERSION);
+        dump.extend(PBKDF2_ROUNDS.to_be_bytes());
+        dump.extend(salt);
+        dump.extend(nonce);
+        dump.extend(ciphertext);
+
+        Ok(dump)
+    }
+
+    /// Decrypts keys dumped with the same password
+    pub fn parse(data: &[u8], password: &[u8]) -> Result<Wallet, WalletError> {
+        if data.len() < KEYSTORE_HEADER_SIZE {
+            return Err(Report::new(WalletError::Wallet(WalletErrorKind::Decrypt))
+                .attach_printable("keystore is too short"));
+        }
+
+        if data[0] != KEYSTORE_VERSION {
+            return Err(Report::new(WalletError::Wallet(WalletErrorKind::Decrypt))
+                .attach_printable(format!("unknown keystore version {}", data[0])));
+        }
+        let mut offset: usize = 1;
+
+        // parsing rounds, version 1 keystores are always written with PBKDF2_ROUNDS
+        let rounds = u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap());
+        if rounds != PBKDF2_ROUNDS {
+            return Err(Report::new(WalletError::Wallet(WalletErrorKind::Decrypt))
+                .attach_printable(format!("unexpected pbkdf2 rounds {}", rounds)));
+        }
+        offset += 4;
+
+        // parsing salt
+        let salt = &data[offset..offset + SALT_SIZE];
+        offset += SALT_SIZE;
+
+        // parsing nonce
+        let nonce: [u8; 12] = data[offset..offset + NONCE_SIZE].try_into().unwrap();
+        offset += NONCE_SIZE;
+
+        let key = derive_key(password, salt, rounds);
+        let cipher = Aes256Gcm::new(&key.into());
+
+        let plaintext = cipher
+            .decrypt(&nonce.into(), &data[offset..])
+            .map_err(|_| {
+                Report::new(WalletError::Wallet(WalletErrorKind::Decrypt))
+                    .attach_printable("wrong password or corrupted keystore")
+            })?;
+
+        if plaintext.len() % 32 != 0 {
+            return Err(Report::new(WalletError::Wallet(WalletErrorKind::Decrypt))
+                .attach_printable("wrong size of keys"));
+        }
+
+        let mut wallet = Wallet::new();
+        for secret_key in plaintext.chunks(32) {
+            wallet
+                .import_key(secret_key.try_into().unwrap())
+                .change_context(WalletError::Wallet(WalletErrorKind::Decrypt))?;
+        }
+
+        Ok(wallet)
+    }
+
+    /// Writes encrypted keystore to the file
+    pub fn save(&self, path: &Path, password: &[u8]) -> Result<(), WalletError> {
+        let dump = self
+            .dump(password)
+            .change_context(WalletError::Wallet(WalletErrorKind::Save))?;
+
+        fs::write(path, dump)
+            .report()
+            .change_context(WalletError::Wallet(WalletErrorKind::Save))
+            .attach_printable(format!("failed to write keystore to {}", path.display()))
+    }
+
+    /// Reads encrypted keystore from the file
+    pub fn load(path: &Path, password: &[u8]) -> Result<Wallet, WalletError> {
+        let data = fs::read(path)
+            .report()
+            .change_context(WalletError::Wallet(WalletErrorKind::Load))
+            .attach_printable(format!("failed to read keystore from {}", path.display()))?;
+
+        Wallet::parse(&data, password).change_context(WalletError::Wallet(WalletErrorKind::Load))
+    }
+}
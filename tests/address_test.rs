@@ -0,0 +1,32 @@
+use blockchaintree::address::{Address, Network};
+use blockchaintree::wallet::address_from_secret_key;
+
+#[test]
+fn address_test() {
+    let key = address_from_secret_key(&[7u8; 32]).unwrap();
+    let address = Address::new(key, Network::Main);
+
+    let encoded = address.to_string();
+    assert!(encoded.starts_with("bct1"));
+    assert_eq!(encoded.parse::<Address>().unwrap(), address);
+    assert_eq!(encoded.to_uppercase().parse::<Address>().unwrap(), address);
+
+    let test_address = Address::new(key, Network::Test);
+    let encoded_test = test_address.to_string();
+    assert!(encoded_test.starts_with("tbct1"));
+    assert_eq!(
+        encoded_test.parse::<Address>().unwrap().get_network(),
+        Network::Test
+    );
+
+    // single changed symbol breaks the checksum
+    let mut corrupted = encoded.into_bytes();
+    let last = corrupted.len() - 1;
+    corrupted[last] = if corrupted[last] == b'q' { b'p' } else { b'q' };
+    assert!(String::from_utf8(corrupted)
+        .unwrap()
+        .parse::<Address>()
+        .is_err());
+
+    assert!("btc1qqqqqq".parse::<Address>().is_err());
+}
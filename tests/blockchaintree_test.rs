@@ -1,15 +1,27 @@
-use blockchaintree::block::{self, BasicInfo};
+use blockchaintree::block::{self, BasicInfo, TransactionBlock};
+use blockchaintree::blockchaintree::BlockChainTree;
+use blockchaintree::merkletree::MerkleTree;
+use blockchaintree::transaction::Transaction;
+use blockchaintree::wallet::address_from_secret_key;
 use blockchaintree::{self, transaction::Transactionable};
 use num_bigint::ToBigUint;
+use std::fs;
 
 static SENDER: &[u8; 33] = b"123456789012345678901234567890123";
 static RECIEVER: &[u8; 33] = b"123456789012345678901234567890123";
 static SIGNATURE: &[u8; 64] = b"1234567890123456789012345678901234567890123456789012345678901234";
 static PREV_HASH: &[u8; 32] = b"12345678901234567890123456789012";
+static CHAIN_PATH: &str = "./chain_test_tree/";
+static TRANSACTION_BLOCK_HEADER_PATH: &str = "./block_header_test_tree/";
+static BLOCK_BINDING_PATH: &str = "./block_binding_test_tree/";
+static LEGACY_REPLAY_PATH: &str = "./legacy_replay_test_tree/";
+static STALE_NONCE_PATH: &str = "./stale_nonce_test_tree/";
+static PRIVATE_KEY: &[u8; 32] = &[17u8; 32];
 
 #[tokio::test]
 async fn chain_test() {
-    let mut blockchain = blockchaintree::blockchaintree::BlockChainTree::without_config().unwrap();
+    let _ = fs::remove_dir_all(CHAIN_PATH);
+    let mut blockchain = BlockChainTree::without_config_at(CHAIN_PATH).unwrap();
 
     let default_info = BasicInfo::new(
         500,
@@ -18,6 +30,7 @@ async fn chain_test() {
         [1u8; 32],
         0,
         [5u8; 32],
+        [0u8; 32],
     );
     let tr = blockchaintree::transaction::Transaction::new(
         SENDER,
@@ -33,14 +46,272 @@ async fn chain_test() {
     {
         chain
     } else {
-        blockchaintree::blockchaintree::BlockChainTree::create_derivative_chain(
-            SENDER, PREV_HASH, 0,
-        )
-        .unwrap()
+        blockchain
+            .create_derivative_chain(SENDER, PREV_HASH, 0)
+            .unwrap()
     };
 
     derivative_chain.add_block(&block).await.unwrap();
 
     let block_db = derivative_chain.find_by_height(0).unwrap().unwrap();
     assert_eq!(block_db.payment_transaction.get_sender(), SENDER);
+
+    drop(blockchain);
+    let _ = fs::remove_dir_all(CHAIN_PATH);
+}
+
+fn empty_block(blockchain: &mut BlockChainTree, height: u64, timestamp: u64) -> TransactionBlock {
+    let prev_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+
+    let mut merkle_tree = MerkleTree::new();
+    merkle_tree.add_objects(Vec::new());
+    let merkle_tree_root = *merkle_tree.get_root();
+
+    let transactions: Vec<Box<dyn Transactionable>> = Vec::new();
+    let state_root = blockchain.calculate_state_root(&transactions).unwrap();
+
+    let info = BasicInfo::new(
+        timestamp,
+        0u64.to_biguint().unwrap(),
+        prev_hash,
+        [0u8; 32],
+        height,
+        [0xffu8; 32],
+        state_root,
+    );
+    TransactionBlock::new(
+        transactions,
+        0u64.to_biguint().unwrap(),
+        info,
+        merkle_tree_root,
+    )
+}
+
+#[tokio::test]
+async fn transaction_block_header_test() {
+    let _ = fs::remove_dir_all(TRANSACTION_BLOCK_HEADER_PATH);
+    let mut blockchain = BlockChainTree::without_config_at(TRANSACTION_BLOCK_HEADER_PATH).unwrap();
+    let height = blockchain.get_main_chain().get_height();
+
+    // header height has to be the height of the next block
+    for wrong_height in [height + 1, height + 1000] {
+        let block = empty_block(&mut blockchain, wrong_height, 121212);
+        assert!(blockchain.add_transaction_block(block).await.is_err());
+    }
+
+    // timestamps far in the future would unlock transactions early
+    let block = empty_block(&mut blockchain, height, u64::MAX / 2);
+    assert!(blockchain.add_transaction_block(block).await.is_err());
+    assert_eq!(blockchain.get_main_chain().get_height(), height);
+
+    let block = empty_block(&mut blockchain, height, 121212);
+    blockchain.add_transaction_block(block).await.unwrap();
+    assert_eq!(blockchain.get_main_chain().get_height(), height + 1);
+
+    drop(blockchain);
+    let _ = fs::remove_dir_all(TRANSACTION_BLOCK_HEADER_PATH);
+}
+
+fn transfer_block(
+    blockchain: &mut BlockChainTree,
+    prev_hash: [u8; 32],
+    tamper_root: bool,
+) -> TransactionBlock {
+    let sender = address_from_secret_key(PRIVATE_KEY).unwrap();
+    let mut tr = Transaction::new_versioned(
+        &sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        0,
+    );
+    tr.sign(&prev_hash, PRIVATE_KEY).unwrap();
+
+    let mut merkle_tree = MerkleTree::new();
+    merkle_tree.add_objects(vec![&tr.get_id()]);
+    let mut merkle_tree_root = *merkle_tree.get_root();
+    if tamper_root {
+        merkle_tree_root[0] ^= 1;
+    }
+
+    let height = blockchain.get_main_chain().get_height();
+    let transactions: Vec<Box<dyn Transactionable>> = vec![Box::new(tr)];
+    let state_root = blockchain.calculate_state_root(&transactions).unwrap();
+
+    let info = BasicInfo::new(
+        121212,
+        0u64.to_biguint().unwrap(),
+        prev_hash,
+        [0u8; 32],
+        height,
+        [0xffu8; 32],
+        state_root,
+    );
+    TransactionBlock::new(
+        transactions,
+        1u64.to_biguint().unwrap(),
+        info,
+        merkle_tree_root,
+    )
+}
+
+#[tokio::test]
+async fn block_binding_test() {
+    let _ = fs::remove_dir_all(BLOCK_BINDING_PATH);
+    let mut blockchain = BlockChainTree::without_config_at(BLOCK_BINDING_PATH).unwrap();
+    let sender = address_from_secret_key(PRIVATE_KEY).unwrap();
+
+    blockchain
+        .add_funds(&sender, &1000u64.to_biguint().unwrap())
+        .await
+        .unwrap();
+    let last_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+
+    // root that doesn't commit to the transactions
+    let block = transfer_block(&mut blockchain, last_hash, true);
+    assert!(blockchain.add_transaction_block(block).await.is_err());
+
+    // block built on top of another block
+    let block = transfer_block(&mut blockchain, [3u8; 32], false);
+    assert!(blockchain.add_transaction_block(block).await.is_err());
+
+    // nothing was written by the refused blocks
+    assert_eq!(blockchain.get_main_chain().get_height(), 0);
+    assert_eq!(
+        blockchain.get_funds(&sender).unwrap(),
+        1000u64.to_biguint().unwrap()
+    );
+    assert_eq!(blockchain.get_nonce(&sender).unwrap(), 0);
+
+    let block = transfer_block(&mut blockchain, last_hash, false);
+    blockchain.add_transaction_block(block).await.unwrap();
+    assert_eq!(blockchain.get_main_chain().get_height(), 1);
+
+    drop(blockchain);
+    let _ = fs::remove_dir_all(BLOCK_BINDING_PATH);
+}
+
+fn legacy_block(
+    blockchain: &mut BlockChainTree,
+    transactions: Vec<Box<dyn Transactionable>>,
+) -> TransactionBlock {
+    let prev_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+    let height = blockchain.get_main_chain().get_height();
+
+    let hashes: Vec<[u8; 32]> = transactions
+        .iter()
+        .map(|transaction| transaction.get_id())
+        .collect();
+    let mut merkle_tree = MerkleTree::new();
+    merkle_tree.add_objects(hashes.iter().collect());
+    let merkle_tree_root = *merkle_tree.get_root();
+
+    // state root of refused blocks doesn't matter
+    let state_root = blockchain
+        .calculate_state_root(&transactions)
+        .unwrap_or([0u8; 32]);
+
+    let info = BasicInfo::new(
+        121212,
+        0u64.to_biguint().unwrap(),
+        prev_hash,
+        [0u8; 32],
+        height,
+        [0xffu8; 32],
+        state_root,
+    );
+    TransactionBlock::new(
+        transactions,
+        0u64.to_biguint().unwrap(),
+        info,
+        merkle_tree_root,
+    )
+}
+
+#[tokio::test]
+async fn legacy_replay_test() {
+    let _ = fs::remove_dir_all(LEGACY_REPLAY_PATH);
+    let mut blockchain = BlockChainTree::without_config_at(LEGACY_REPLAY_PATH).unwrap();
+    let sender = address_from_secret_key(PRIVATE_KEY).unwrap();
+
+    blockchain
+        .add_funds(&sender, &1000u64.to_biguint().unwrap())
+        .await
+        .unwrap();
+
+    let prev_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+    let legacy = || {
+        let mut tr = Transaction::new(
+            &sender,
+            RECIEVER,
+            121212,
+            &[0u8; 64],
+            100u64.to_biguint().unwrap(),
+        );
+        tr.sign(&prev_hash, PRIVATE_KEY).unwrap();
+        Box::new(tr) as Box<dyn Transactionable>
+    };
+
+    // the same transfer twice in one block
+    let block = legacy_block(&mut blockchain, vec![legacy(), legacy()]);
+    assert!(blockchain.add_transaction_block(block).await.is_err());
+
+    let block = legacy_block(&mut blockchain, vec![legacy()]);
+    blockchain.add_transaction_block(block).await.unwrap();
+
+    // signature is bound to the previous hash, which is the last block only once
+    let block = legacy_block(&mut blockchain, vec![legacy()]);
+    assert!(blockchain.add_transaction_block(block).await.is_err());
+    assert_eq!(
+        blockchain.get_funds(&sender).unwrap(),
+        900u64.to_biguint().unwrap()
+    );
+
+    drop(blockchain);
+    let _ = fs::remove_dir_all(LEGACY_REPLAY_PATH);
+}
+
+#[tokio::test]
+async fn stale_nonce_eviction_test() {
+    let _ = fs::remove_dir_all(STALE_NONCE_PATH);
+    let mut blockchain = BlockChainTree::without_config_at(STALE_NONCE_PATH).unwrap();
+    let sender = address_from_secret_key(PRIVATE_KEY).unwrap();
+
+    blockchain
+        .add_funds(&sender, &1000u64.to_biguint().unwrap())
+        .await
+        .unwrap();
+    let last_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+
+    for nonce in 0..2 {
+        let mut tr = Transaction::new_versioned(
+            &sender,
+            RECIEVER,
+            121212,
+            &[0u8; 64],
+            50u64.to_biguint().unwrap(),
+            2u64.to_biguint().unwrap(),
+            nonce,
+        );
+        tr.sign(&last_hash, PRIVATE_KEY).unwrap();
+        blockchain.new_transaction(tr).await.unwrap();
+    }
+    assert_eq!(blockchain.get_pool().len(), 2);
+
+    // block produced elsewhere takes the nonce of the first pending transfer
+    let block = transfer_block(&mut blockchain, last_hash, false);
+    blockchain.add_transaction_block(block).await.unwrap();
+    assert_eq!(blockchain.get_nonce(&sender).unwrap(), 1);
+
+    // conflicting transfer and the one depending on it don't reserve coins anymore
+    assert!(blockchain.get_pool().is_empty());
+    assert_eq!(
+        blockchain.get_pool().get_pending_spent(&sender),
+        0u64.to_biguint().unwrap()
+    );
+
+    drop(blockchain);
+    let _ = fs::remove_dir_all(STALE_NONCE_PATH);
 }
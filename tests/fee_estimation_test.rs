@@ -0,0 +1,118 @@
+use blockchaintree::block::{BasicInfo, TransactionBlock};
+use blockchaintree::fee_estimation::{
+    block_min_fee_rate, estimate_fee_rate, fee_for_size, MIN_FEE_RATE,
+};
+use blockchaintree::mempool::{fee_rate, Mempool};
+use blockchaintree::transaction::{Transaction, Transactionable};
+use num_bigint::{BigUint, ToBigUint};
+
+static SENDER: &[u8; 33] = b"123456789012345678901234567890123";
+static RECIEVER: &[u8; 33] = b"abcdefghijklmnopqrstuvwxyzabcdefg";
+
+fn rates(rates: &[u64]) -> Vec<BigUint> {
+    rates
+        .iter()
+        .map(|rate| rate.to_biguint().unwrap())
+        .collect()
+}
+
+#[test]
+fn fee_estimation_test() {
+    // nothing to compete with
+    assert_eq!(
+        estimate_fee_rate(&[], &[], 1, 2),
+        MIN_FEE_RATE.to_biguint().unwrap()
+    );
+
+    // pool is larger than the target blocks, the last fitting transaction is outbid
+    let pool = rates(&[90, 80, 70, 60, 50]);
+    assert_eq!(
+        estimate_fee_rate(&pool, &[], 1, 2),
+        81u64.to_biguint().unwrap()
+    );
+    assert_eq!(
+        estimate_fee_rate(&pool, &[], 2, 2),
+        61u64.to_biguint().unwrap()
+    );
+    assert_eq!(
+        estimate_fee_rate(&pool, &[], 3, 2),
+        1u64.to_biguint().unwrap()
+    );
+
+    // further targets take lower rates of the recent blocks
+    let blocks = rates(&[40, 10, 30, 20, 50]);
+    assert_eq!(
+        estimate_fee_rate(&[], &blocks, 1, 2),
+        30u64.to_biguint().unwrap()
+    );
+    assert_eq!(
+        estimate_fee_rate(&[], &blocks, 4, 2),
+        20u64.to_biguint().unwrap()
+    );
+
+    // the highest of both estimates wins
+    assert_eq!(
+        estimate_fee_rate(&pool, &blocks, 2, 2),
+        61u64.to_biguint().unwrap()
+    );
+
+    // fee reaching the rate
+    let transaction = |fee: u64, nonce: u64| {
+        Transaction::new_versioned(
+            SENDER,
+            RECIEVER,
+            121212,
+            &[0u8; 64],
+            100u64.to_biguint().unwrap(),
+            fee.to_biguint().unwrap(),
+            nonce,
+        )
+    };
+    let rate = 1000u64.to_biguint().unwrap();
+    let fee = fee_for_size(&rate, transaction(255, 0).get_dump_size());
+    let tr = transaction(fee.iter_u64_digits().next().unwrap(), 0);
+    assert!(fee_rate(&tr) >= rate);
+    assert!(fee_rate(&transaction(100, 0)) < rate);
+
+    // pool reports fee rates from the highest
+    let mut pool = Mempool::default();
+    pool.add(Box::new(transaction(10, 0)), 0).unwrap();
+    pool.add(Box::new(transaction(200, 1)), 0).unwrap();
+    let pool_rates = pool.get_fee_rates();
+    assert_eq!(pool_rates.len(), 2);
+    assert!(pool_rates[0] > pool_rates[1]);
+
+    // only fee paying transfers set the lowest rate of the block
+    let block = |transactions: Vec<Box<dyn Transactionable>>| {
+        let default_info = BasicInfo::new(
+            500,
+            1000u64.to_biguint().unwrap(),
+            [0u8; 32],
+            [1u8; 32],
+            0,
+            [5u8; 32],
+            [0u8; 32],
+        );
+        TransactionBlock::new(transactions, BigUint::default(), default_info, [0u8; 32])
+    };
+    let legacy = Transaction::new(
+        SENDER,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+    );
+    let paying = block(vec![
+        Box::new(transaction(200, 0)),
+        Box::new(transaction(0, 1)),
+        Box::new(legacy),
+    ]);
+    assert_eq!(
+        block_min_fee_rate(&paying),
+        Some(fee_rate(&transaction(200, 0)))
+    );
+    assert_eq!(
+        block_min_fee_rate(&block(vec![Box::new(transaction(0, 0))])),
+        None
+    );
+}
@@ -0,0 +1,62 @@
+use blockchaintree::errors::WalletError;
+use blockchaintree::hd::{address_path, parse_path, ExtendedKey, HARDENED};
+use blockchaintree::wallet::{generate_mnemonic, mnemonic_to_seed, Wallet};
+use error_stack::Result;
+use hex::ToHex;
+
+static PHRASE: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+#[test]
+fn hd_test() {
+    // BIP32 test vector 1
+    let seed: Vec<u8> = (0u8..16).collect();
+    let master = ExtendedKey::from_seed(&seed).unwrap();
+    assert_eq!(
+        master.get_secret_key().encode_hex::<String>(),
+        "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+    );
+
+    let path = parse_path("m/0'/1").unwrap();
+    assert_eq!(path, vec![HARDENED, 1]);
+    assert_eq!(
+        master
+            .derive_path(&path)
+            .unwrap()
+            .get_secret_key()
+            .encode_hex::<String>(),
+        "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368"
+    );
+    assert!(parse_path("44'/0").is_err());
+
+    // BIP39 test vector
+    assert_eq!(
+        hex::encode(&mnemonic_to_seed(PHRASE, "TREZOR").unwrap()[..8]),
+        "c55257c360c07c72"
+    );
+    assert!(mnemonic_to_seed("abandon abandon", "").is_err());
+    assert_eq!(generate_mnemonic(24).unwrap().split(' ').count(), 24);
+    assert_eq!(generate_mnemonic(12).unwrap().split(' ').count(), 12);
+    for words in [0, 13, 27, 48] {
+        assert!(generate_mnemonic(words).is_err());
+    }
+
+    // recovery finds used addresses within the gap limit
+    let master = ExtendedKey::from_seed(&mnemonic_to_seed(PHRASE, "").unwrap()).unwrap();
+    let address = |index: u32| {
+        master
+            .derive_path(&address_path(index))
+            .unwrap()
+            .get_address()
+            .unwrap()
+    };
+    let used = [address(0), address(15), address(40)];
+
+    let wallet = Wallet::recover(PHRASE, "", |addr| -> Result<bool, WalletError> {
+        Ok(used.contains(addr))
+    })
+    .unwrap();
+    assert_eq!(wallet.len(), 2);
+    assert!(wallet.contains(&used[0]));
+    assert!(wallet.contains(&used[1]));
+}
@@ -0,0 +1,120 @@
+use blockchaintree::block::{BasicInfo, TransactionBlock};
+use blockchaintree::blockchaintree::BlockChainTree;
+use blockchaintree::history::HistoryCursor;
+use blockchaintree::merkletree::MerkleTree;
+use blockchaintree::transaction::{Transaction, Transactionable};
+use blockchaintree::wallet::address_from_secret_key;
+use num_bigint::ToBigUint;
+use std::fs;
+
+static RECIEVER: &[u8; 33] = b"abcdefghijklmnopqrstuvwxyzabcdefg";
+static PRIVATE_KEY: &[u8; 32] = &[11u8; 32];
+static TREE_PATH: &str = "./history_test_tree/";
+
+fn transfer(sender: &[u8; 33], nonce: u64) -> Transaction {
+    let mut tr = Transaction::new_versioned(
+        sender,
+        RECIEVER,
+        121212 + nonce,
+        &[0u8; 64],
+        10u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        nonce,
+    );
+    tr.sign(&[0u8; 32], PRIVATE_KEY).unwrap();
+    tr
+}
+
+async fn apply(blockchain: &mut BlockChainTree, tr: Transaction) {
+    let prev_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+    let height = blockchain.get_main_chain().get_height();
+
+    let mut merkle_tree = MerkleTree::new();
+    merkle_tree.add_objects(vec![&tr.get_id()]);
+    let merkle_tree_root = *merkle_tree.get_root();
+
+    let transactions: Vec<Box<dyn Transactionable>> = vec![Box::new(tr)];
+    let state_root = blockchain.calculate_state_root(&transactions).unwrap();
+
+    let info = BasicInfo::new(
+        121212,
+        0u64.to_biguint().unwrap(),
+        prev_hash,
+        [0u8; 32],
+        height,
+        [0xffu8; 32],
+        state_root,
+    );
+    let block = TransactionBlock::new(
+        transactions,
+        1u64.to_biguint().unwrap(),
+        info,
+        merkle_tree_root,
+    );
+    blockchain.add_transaction_block(block).await.unwrap();
+}
+
+#[tokio::test]
+async fn address_history_test() {
+    let _ = fs::remove_dir_all(TREE_PATH);
+    let mut blockchain = BlockChainTree::without_config_at(TREE_PATH).unwrap();
+    let sender = address_from_secret_key(PRIVATE_KEY).unwrap();
+
+    blockchain
+        .add_funds(&sender, &1000u64.to_biguint().unwrap())
+        .await
+        .unwrap();
+
+    let first_height = blockchain.get_main_chain().get_height();
+    for nonce in 0..3 {
+        apply(&mut blockchain, transfer(&sender, nonce)).await;
+    }
+    blockchain
+        .new_transaction(transfer(&sender, 3))
+        .await
+        .unwrap();
+
+    // pending transaction goes first, then included ones from the newest
+    let page = blockchain
+        .address_history(&sender, HistoryCursor::default(), 2)
+        .unwrap();
+    let entries = page.get_entries();
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].is_pending());
+    assert_eq!(entries[0].get_transaction().get_nonce(), Some(3));
+    assert_eq!(entries[1].get_location(), Some((first_height + 2, 0)));
+    assert_eq!(
+        page.get_next(),
+        Some(HistoryCursor::Included(first_height + 2, 0))
+    );
+
+    let page = blockchain
+        .address_history(&sender, page.get_next().unwrap(), 2)
+        .unwrap();
+    let nonces: Vec<Option<u64>> = page
+        .get_entries()
+        .iter()
+        .map(|entry| entry.get_transaction().get_nonce())
+        .collect();
+    assert_eq!(nonces, vec![Some(1), Some(0)]);
+
+    let page = blockchain
+        .address_history(&sender, page.get_next().unwrap(), 2)
+        .unwrap();
+    assert!(page.get_entries().is_empty());
+    assert_eq!(page.get_next(), None);
+
+    // receiver sees the same transfers
+    let page = blockchain
+        .address_history(RECIEVER, HistoryCursor::default(), 10)
+        .unwrap();
+    assert!(page.get_entries().len() >= 4);
+
+    // empty pages would never move the cursor
+    assert!(blockchain
+        .address_history(&sender, HistoryCursor::default(), 0)
+        .is_err());
+
+    drop(blockchain);
+    let _ = fs::remove_dir_all(TREE_PATH);
+}
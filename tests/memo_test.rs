@@ -0,0 +1,148 @@
+use blockchaintree::address::Network;
+use blockchaintree::block::{BasicInfo, SumTransactionBlock, TransactionBlock};
+use blockchaintree::blockchaintree::Chain;
+use blockchaintree::memo::{check_payload_fee, min_payload_fee, MemoTransaction, MAX_PAYLOAD_SIZE};
+use blockchaintree::pool_journal::parse_transaction;
+use blockchaintree::tools;
+use blockchaintree::transaction::Transactionable;
+use blockchaintree::wallet::address_from_secret_key;
+use num_bigint::ToBigUint;
+use std::fs;
+
+static RECIEVER: &[u8; 33] = b"123456789012345678901234567890123";
+static PREV_HASH: &[u8; 32] = b"12345678901234567890123456789012";
+static PRIVATE_KEY: &[u8; 32] = &[7u8; 32];
+static CHAIN_PATH: &str = "./memo_test_chain/";
+
+#[test]
+fn memo_test() {
+    let sender = address_from_secret_key(PRIVATE_KEY).unwrap();
+    let payload = tools::hash(b"document").to_vec();
+
+    let mut tr = MemoTransaction::new(
+        &sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        payload.clone(),
+        0u64.to_biguint().unwrap(),
+        min_payload_fee(payload.len()),
+        0,
+    )
+    .unwrap();
+    tr.sign(PREV_HASH, PRIVATE_KEY).unwrap();
+    assert!(tr.verify(PREV_HASH).unwrap());
+    assert!(check_payload_fee(&tr));
+    assert_eq!(tr.get_payload_hash(), tools::hash(&payload));
+
+    let dump = tr.dump().unwrap();
+    assert_eq!(dump.len(), tr.get_dump_size());
+
+    let parsed = parse_transaction(&dump).unwrap();
+    assert_eq!(parsed.get_payload(), Some(payload.as_slice()));
+    assert_eq!(parsed.hash(PREV_HASH), tr.hash(PREV_HASH));
+    assert!(parsed.verify(PREV_HASH).unwrap());
+
+    // payload is signed
+    let mut changed = payload.clone();
+    changed[0] ^= 1;
+    let forged = MemoTransaction::new(
+        &sender,
+        RECIEVER,
+        121212,
+        tr.get_signature(),
+        changed,
+        0u64.to_biguint().unwrap(),
+        min_payload_fee(payload.len()),
+        0,
+    )
+    .unwrap();
+    assert!(!forged.verify(PREV_HASH).unwrap());
+
+    // fee has to cover every byte of the payload
+    let cheap = MemoTransaction::new(
+        &sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        payload.clone(),
+        0u64.to_biguint().unwrap(),
+        min_payload_fee(payload.len() - 1),
+        0,
+    )
+    .unwrap();
+    assert!(!check_payload_fee(&cheap));
+
+    // payload size is bounded
+    assert!(MemoTransaction::new(
+        &sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        vec![0u8; MAX_PAYLOAD_SIZE + 1],
+        0u64.to_biguint().unwrap(),
+        min_payload_fee(MAX_PAYLOAD_SIZE + 1),
+        0,
+    )
+    .is_err());
+}
+
+#[tokio::test]
+async fn payload_anchor_test() {
+    let _ = fs::remove_dir_all(CHAIN_PATH);
+    let mut chain = Chain::new_without_config(CHAIN_PATH, &[0u8; 32], Network::Main).unwrap();
+
+    let sender = address_from_secret_key(PRIVATE_KEY).unwrap();
+    let payload = tools::hash(b"anchored document").to_vec();
+    let payload_hash = tools::hash(&payload);
+
+    let block = |nonce: u64| {
+        let tr = MemoTransaction::new(
+            &sender,
+            RECIEVER,
+            121212,
+            &[0u8; 64],
+            payload.clone(),
+            0u64.to_biguint().unwrap(),
+            min_payload_fee(payload.len()),
+            nonce,
+        )
+        .unwrap();
+        let info = BasicInfo::new(
+            121212,
+            0u64.to_biguint().unwrap(),
+            [0u8; 32],
+            [0u8; 32],
+            0,
+            [0xffu8; 32],
+            [0u8; 32],
+        );
+        let fee = min_payload_fee(payload.len());
+        SumTransactionBlock::new(
+            Some(TransactionBlock::new(
+                vec![Box::new(tr)],
+                fee,
+                info,
+                [0u8; 32],
+            )),
+            None,
+        )
+    };
+
+    let first_height = chain.get_height();
+    chain.add_block(&block(0)).await.unwrap();
+    assert_eq!(
+        chain.find_by_payload_hash(&payload_hash).unwrap(),
+        Some(first_height)
+    );
+
+    // anchoring the same payload again keeps the earliest height
+    chain.add_block(&block(1)).await.unwrap();
+    assert_eq!(
+        chain.find_by_payload_hash(&payload_hash).unwrap(),
+        Some(first_height)
+    );
+
+    drop(chain);
+    let _ = fs::remove_dir_all(CHAIN_PATH);
+}
@@ -0,0 +1,153 @@
+use blockchaintree::mempool::{min_replacement_fee, Mempool, MAX_REPLACEMENTS};
+use blockchaintree::transaction::{Transaction, Transactionable};
+use num_bigint::ToBigUint;
+
+static FIRST: &[u8; 33] = b"123456789012345678901234567890123";
+static SECOND: &[u8; 33] = b"abcdefghijklmnopqrstuvwxyzabcdefg";
+static RECIEVER: &[u8; 33] = b"000000000000000000000000000000000";
+
+fn transaction(sender: &[u8; 33], fee: u64, nonce: u64) -> Box<dyn Transactionable> {
+    Box::new(Transaction::new_versioned(
+        sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+        fee.to_biguint().unwrap(),
+        nonce,
+    ))
+}
+
+#[test]
+fn mempool_test() {
+    let mut pool = Mempool::new(3, 1024 * 1024, 100);
+
+    let hash = pool.add(transaction(FIRST, 10, 0), 0).unwrap();
+    assert!(pool.contains(&hash));
+
+    // duplicates and nonce conflicts are rejected
+    assert!(pool.add(transaction(FIRST, 10, 0), 0).is_err());
+    assert!(pool.add(transaction(FIRST, 20, 0), 0).is_err());
+
+    pool.add(transaction(FIRST, 5000, 1), 10).unwrap();
+    pool.add(transaction(SECOND, 200, 0), 20).unwrap();
+    assert_eq!(pool.len(), 3);
+    assert_eq!(pool.get_by_sender(FIRST).len(), 2);
+    assert_eq!(pool.get_by_receiver(RECIEVER).len(), 3);
+    // amounts and fees are reserved until transactions leave the pool
+    assert_eq!(pool.get_pending_spent(FIRST), 5210u64.to_biguint().unwrap());
+
+    // full pool evicts the cheapest transaction that doesn't leave a nonce gap
+    assert!(pool.add(transaction(SECOND, 1, 1), 30).is_err());
+    pool.add(transaction(SECOND, 9000, 1), 30).unwrap();
+    assert_eq!(pool.len(), 3);
+    assert!(pool.contains(&hash));
+    assert!(pool.find_by_nonce(FIRST, 1).is_none());
+    // evicted transactions are reported once
+    assert_eq!(pool.take_evicted().len(), 1);
+    assert!(pool.take_evicted().is_empty());
+
+    // old transactions expire
+    assert_eq!(pool.remove_expired(115).len(), 1);
+    assert!(!pool.contains(&hash));
+
+    // transactions the new one depends on are never evicted
+    pool.add(transaction(FIRST, 10, 0), 120).unwrap();
+    pool.add(transaction(FIRST, 9500, 1), 120).unwrap();
+    assert!(pool.find_by_nonce(SECOND, 1).is_none());
+
+    let best = pool.pop_best(10);
+    let order: Vec<(&[u8; 33], u64)> = best
+        .iter()
+        .map(|tr| (tr.get_sender(), tr.get_nonce().unwrap()))
+        .collect();
+    assert_eq!(order, vec![(SECOND, 0), (FIRST, 0), (FIRST, 1)]);
+    assert!(pool.is_empty());
+    assert_eq!(pool.get_size_in_bytes(), 0);
+    assert_eq!(pool.get_pending_spent(FIRST), 0u64.to_biguint().unwrap());
+}
+
+#[test]
+fn expiry_height_test() {
+    let mut pool = Mempool::default();
+    let expiring = |nonce: u64, expiry_height: u64| -> Box<dyn Transactionable> {
+        Box::new(
+            Transaction::new_versioned(
+                FIRST,
+                RECIEVER,
+                121212,
+                &[0u8; 64],
+                100u64.to_biguint().unwrap(),
+                10u64.to_biguint().unwrap(),
+                nonce,
+            )
+            .with_expiry_height(expiry_height),
+        )
+    };
+
+    pool.add(expiring(0, 5), 0).unwrap();
+    pool.add(expiring(1, 100), 0).unwrap();
+    // zero expiry height never expires
+    let never = Transaction::new_versioned(
+        SECOND,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+        10u64.to_biguint().unwrap(),
+        0,
+    );
+    assert_eq!(never.get_expiry_height(), None);
+    pool.add(Box::new(never), 0).unwrap();
+
+    assert!(pool.remove_expired_at_height(5).is_empty());
+
+    // successors can't be included without the expired transaction
+    let removed = pool.remove_expired_at_height(6);
+    assert_eq!(removed.len(), 2);
+    assert_eq!(pool.len(), 1);
+    assert!(pool.find_by_nonce(SECOND, 0).is_some());
+}
+
+#[test]
+fn replacement_test() {
+    let mut pool = Mempool::new(10, 1024 * 1024, 100);
+
+    let first_hash = pool.add(transaction(FIRST, 100, 0), 0).unwrap();
+    pool.add(transaction(FIRST, 100, 1), 0).unwrap();
+    assert_eq!(
+        min_replacement_fee(&100u64.to_biguint().unwrap()),
+        110u64.to_biguint().unwrap()
+    );
+
+    // fee has to grow by the minimal bump
+    assert!(pool.replace(transaction(FIRST, 100, 0), 0).is_err());
+    assert!(pool.replace(transaction(FIRST, 109, 0), 0).is_err());
+    // only pending nonces can be replaced
+    assert!(pool.replace(transaction(FIRST, 500, 2), 0).is_err());
+    assert!(pool.replace(transaction(SECOND, 500, 0), 0).is_err());
+
+    let (hash, replaced) = pool.replace(transaction(FIRST, 110, 0), 10).unwrap();
+    assert_eq!(replaced.get_id(), first_hash);
+    assert!(!pool.contains(&first_hash));
+    assert_eq!(pool.find_by_nonce(FIRST, 0), Some(&hash));
+    assert_eq!(pool.len(), 2);
+    // reserved coins follow the replacement
+    assert_eq!(pool.get_pending_spent(FIRST), 410u64.to_biguint().unwrap());
+
+    // amount of replacements is bounded
+    let mut fee = 110u64.to_biguint().unwrap();
+    for _ in 1..MAX_REPLACEMENTS {
+        fee = min_replacement_fee(&fee);
+        let next = transaction(FIRST, fee.iter_u64_digits().next().unwrap(), 0);
+        pool.replace(next, 10).unwrap();
+    }
+    let fee = min_replacement_fee(&fee).iter_u64_digits().next().unwrap();
+    assert!(pool.replace(transaction(FIRST, fee, 0), 10).is_err());
+
+    // nonce leaving the pool resets the counter
+    let hash = *pool.find_by_nonce(FIRST, 0).unwrap();
+    pool.remove(&hash);
+    pool.add(transaction(FIRST, 10, 0), 20).unwrap();
+    pool.replace(transaction(FIRST, 11, 0), 20).unwrap();
+}
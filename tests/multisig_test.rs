@@ -0,0 +1,220 @@
+use blockchaintree::block::{BasicInfo, TransactionBlock};
+use blockchaintree::blockchaintree::BlockChainTree;
+use blockchaintree::merkletree::MerkleTree;
+use blockchaintree::multisig::{MultisigAccount, MultisigRegistration, MultisigTransaction};
+use blockchaintree::pool_journal::parse_transaction;
+use blockchaintree::transaction::Transactionable;
+use blockchaintree::wallet::address_from_secret_key;
+use num_bigint::ToBigUint;
+use std::fs;
+
+static RECIEVER: &[u8; 33] = b"123456789012345678901234567890123";
+static PREV_HASH: &[u8; 32] = b"12345678901234567890123456789012";
+static REGISTRAR_KEY: &[u8; 32] = &[9u8; 32];
+static TREE_PATH: &str = "./multisig_test_tree/";
+
+fn keys(secret_keys: &[[u8; 32]]) -> Vec<[u8; 33]> {
+    secret_keys
+        .iter()
+        .map(|key| address_from_secret_key(key).unwrap())
+        .collect()
+}
+
+#[test]
+fn multisig_test() {
+    let secret_keys = [[1u8; 32], [2u8; 32], [3u8; 32]];
+    let keys = keys(&secret_keys);
+
+    assert!(MultisigAccount::new(4, keys.clone()).is_err());
+    assert!(MultisigAccount::new(1, vec![keys[0], keys[0]]).is_err());
+
+    let account = MultisigAccount::new(2, keys).unwrap();
+    let mut tr = MultisigTransaction::new(
+        &account.get_address(),
+        RECIEVER,
+        121212,
+        100u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        0,
+    );
+    assert!(!tr.verify(PREV_HASH).unwrap());
+
+    // 1 of 2 required signatures
+    tr.sign(PREV_HASH, &secret_keys[2]).unwrap();
+    assert!(tr.verify(PREV_HASH).unwrap());
+    assert!(!tr.is_authorized(&account));
+
+    tr.sign(PREV_HASH, &secret_keys[0]).unwrap();
+    assert!(tr.verify(PREV_HASH).unwrap());
+    assert!(tr.is_authorized(&account));
+
+    let dump = tr.dump().unwrap();
+    assert_eq!(dump.len(), tr.get_dump_size());
+
+    let parsed = parse_transaction(&dump).unwrap();
+    assert_eq!(parsed.get_sender(), tr.get_sender());
+    assert_eq!(parsed.get_nonce(), Some(0));
+    assert_eq!(parsed.hash(PREV_HASH), tr.hash(PREV_HASH));
+    assert!(parsed.verify(PREV_HASH).unwrap());
+    assert!(parsed.get_multisig().unwrap().is_authorized(&account));
+
+    // keys outside of the registered set don't count
+    let mut foreign = tr.clone();
+    foreign.sign(PREV_HASH, &[4u8; 32]).unwrap();
+    assert!(foreign.verify(PREV_HASH).unwrap());
+    assert!(!foreign.is_authorized(&account));
+
+    // key set is bound to the sender address
+    let other = MultisigAccount::new(1, account.get_keys().to_vec()).unwrap();
+    assert!(!tr.is_authorized(&other));
+
+    let registrar = address_from_secret_key(REGISTRAR_KEY).unwrap();
+    let mut registration = MultisigRegistration::new(
+        &registrar,
+        account.clone(),
+        121212,
+        1u64.to_biguint().unwrap(),
+        3,
+    );
+    assert_eq!(registration.get_receiver(), &account.get_address());
+    registration.sign(PREV_HASH, REGISTRAR_KEY).unwrap();
+    assert!(registration.verify(PREV_HASH).unwrap());
+
+    let dump = registration.dump().unwrap();
+    assert_eq!(dump.len(), registration.get_dump_size());
+
+    let parsed = parse_transaction(&dump).unwrap();
+    assert_eq!(parsed.get_id(), registration.get_id());
+    assert_eq!(parsed.get_nonce(), Some(3));
+    assert_eq!(
+        parsed.get_multisig_registration().unwrap().get_account(),
+        &account
+    );
+    assert!(parsed.verify(PREV_HASH).unwrap());
+}
+
+fn multisig_block(
+    blockchain: &mut BlockChainTree,
+    transactions: Vec<Box<dyn Transactionable>>,
+) -> TransactionBlock {
+    let prev_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+    let height = blockchain.get_main_chain().get_height();
+
+    let hashes: Vec<[u8; 32]> = transactions
+        .iter()
+        .map(|transaction| transaction.get_id())
+        .collect();
+    let mut merkle_tree = MerkleTree::new();
+    merkle_tree.add_objects(hashes.iter().collect());
+    let merkle_tree_root = *merkle_tree.get_root();
+
+    // state root of refused blocks doesn't matter
+    let state_root = blockchain
+        .calculate_state_root(&transactions)
+        .unwrap_or([0u8; 32]);
+
+    let info = BasicInfo::new(
+        121212,
+        0u64.to_biguint().unwrap(),
+        prev_hash,
+        [0u8; 32],
+        height,
+        [0xffu8; 32],
+        state_root,
+    );
+    TransactionBlock::new(
+        transactions,
+        1u64.to_biguint().unwrap(),
+        info,
+        merkle_tree_root,
+    )
+}
+
+#[tokio::test]
+async fn multisig_registration_test() {
+    let _ = fs::remove_dir_all(TREE_PATH);
+    let mut blockchain = BlockChainTree::without_config_at(TREE_PATH).unwrap();
+
+    let secret_keys = [[1u8; 32], [2u8; 32], [3u8; 32]];
+    let account = MultisigAccount::new(2, keys(&secret_keys)).unwrap();
+    let address = account.get_address();
+    let registrar = address_from_secret_key(REGISTRAR_KEY).unwrap();
+
+    blockchain
+        .add_funds(&address, &1000u64.to_biguint().unwrap())
+        .await
+        .unwrap();
+    blockchain
+        .add_funds(&registrar, &1000u64.to_biguint().unwrap())
+        .await
+        .unwrap();
+
+    let spend = |prev_hash: &[u8; 32], signers: &[[u8; 32]]| {
+        let mut tr = MultisigTransaction::new(
+            &address,
+            RECIEVER,
+            121212,
+            100u64.to_biguint().unwrap(),
+            1u64.to_biguint().unwrap(),
+            0,
+        );
+        for signer in signers.iter() {
+            tr.sign(prev_hash, signer).unwrap();
+        }
+        Box::new(tr) as Box<dyn Transactionable>
+    };
+
+    // key set has to be registered before the account can spend
+    let prev_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+    let tr = spend(&prev_hash, &secret_keys[..2]);
+    assert!(blockchain.new_transactionable(tr).await.is_err());
+    let block = multisig_block(&mut blockchain, vec![spend(&prev_hash, &secret_keys[..2])]);
+    assert!(blockchain.add_transaction_block(block).await.is_err());
+    assert!(blockchain.get_multisig_account(&address).unwrap().is_none());
+
+    let mut registration = MultisigRegistration::new(
+        &registrar,
+        account.clone(),
+        121212,
+        1u64.to_biguint().unwrap(),
+        0,
+    );
+    registration.sign(&prev_hash, REGISTRAR_KEY).unwrap();
+    let block = multisig_block(&mut blockchain, vec![Box::new(registration)]);
+    blockchain.add_transaction_block(block).await.unwrap();
+    assert_eq!(
+        blockchain.get_multisig_account(&address).unwrap(),
+        Some(account.clone())
+    );
+
+    // the same key set can't be registered twice
+    let prev_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+    let mut registration = MultisigRegistration::new(
+        &registrar,
+        account.clone(),
+        121213,
+        1u64.to_biguint().unwrap(),
+        1,
+    );
+    registration.sign(&prev_hash, REGISTRAR_KEY).unwrap();
+    assert!(blockchain
+        .new_transactionable(Box::new(registration))
+        .await
+        .is_err());
+
+    // signers are checked against the registered keys
+    let tr = spend(&prev_hash, &secret_keys[..1]);
+    assert!(blockchain.new_transactionable(tr).await.is_err());
+    let tr = spend(&prev_hash, &[secret_keys[0], [4u8; 32]]);
+    assert!(blockchain.new_transactionable(tr).await.is_err());
+
+    let block = multisig_block(&mut blockchain, vec![spend(&prev_hash, &secret_keys[1..])]);
+    blockchain.add_transaction_block(block).await.unwrap();
+    assert_eq!(
+        blockchain.get_funds(&address).unwrap(),
+        899u64.to_biguint().unwrap()
+    );
+
+    drop(blockchain);
+    let _ = fs::remove_dir_all(TREE_PATH);
+}
@@ -0,0 +1,146 @@
+use blockchaintree::blockchaintree::BlockChainTree;
+use blockchaintree::pool_journal::PoolJournal;
+use blockchaintree::transaction::{Transaction, Transactionable};
+use blockchaintree::wallet::address_from_secret_key;
+use num_bigint::ToBigUint;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+static SENDER: &[u8; 33] = b"123456789012345678901234567890123";
+static RECIEVER: &[u8; 33] = b"abcdefghijklmnopqrstuvwxyzabcdefg";
+static SIGNATURE: &[u8; 64] = b"1234567890123456789012345678901234567890123456789012345678901234";
+static JOURNAL_PATH: &str = "./pool_journal_test.pool";
+static POOL_PATH: &str = "./popped_journal_test_tree/TRXS_POOL.pool";
+static PRIVATE_KEY: &[u8; 32] = &[13u8; 32];
+static TREE_PATH: &str = "./popped_journal_test_tree/";
+
+#[test]
+fn pool_journal_test() {
+    let path = Path::new(JOURNAL_PATH);
+    let _ = fs::remove_file(path);
+
+    let legacy = Transaction::new(
+        SENDER,
+        RECIEVER,
+        121212,
+        SIGNATURE,
+        2222222288u64.to_biguint().unwrap(),
+    );
+    let versioned = Transaction::new_versioned(
+        SENDER,
+        RECIEVER,
+        121213,
+        SIGNATURE,
+        100u64.to_biguint().unwrap(),
+        10u64.to_biguint().unwrap(),
+        0,
+    );
+    let removed = Transaction::new_versioned(
+        SENDER,
+        RECIEVER,
+        121214,
+        SIGNATURE,
+        100u64.to_biguint().unwrap(),
+        10u64.to_biguint().unwrap(),
+        1,
+    );
+
+    let mut journal = PoolJournal::open(path).unwrap();
+    journal.append_add(&legacy, 1).unwrap();
+    journal.append_add(&removed, 2).unwrap();
+    journal.append_add(&versioned, 3).unwrap();
+    journal.append_remove(&removed.get_id()).unwrap();
+    drop(journal);
+
+    // crash in the middle of an append
+    let size = fs::metadata(path).unwrap().len();
+    let mut file = OpenOptions::new().append(true).open(path).unwrap();
+    file.write_all(&[0, 0, 0, 1, 0, 1, 2, 3]).unwrap();
+    drop(file);
+
+    let mut journal = PoolJournal::open(path).unwrap();
+    let replayed = journal.replay().unwrap();
+    assert_eq!(fs::metadata(path).unwrap().len(), size);
+    assert_eq!(journal.get_records(), 4);
+
+    let dumps: Vec<(u64, Vec<u8>)> = replayed
+        .iter()
+        .map(|(added_at, tr)| (*added_at, tr.dump().unwrap()))
+        .collect();
+    assert_eq!(
+        dumps,
+        vec![(1, legacy.dump().unwrap()), (3, versioned.dump().unwrap())]
+    );
+
+    // compaction keeps only live transactions
+    journal
+        .compact(
+            replayed
+                .iter()
+                .map(|(added_at, tr)| (tr.as_ref(), *added_at)),
+        )
+        .unwrap();
+    assert_eq!(journal.get_records(), 2);
+
+    let mut journal = PoolJournal::open(path).unwrap();
+    let compacted: Vec<(u64, Vec<u8>)> = journal
+        .replay()
+        .unwrap()
+        .iter()
+        .map(|(added_at, tr)| (*added_at, tr.dump().unwrap()))
+        .collect();
+    assert_eq!(compacted, dumps);
+
+    fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn popped_transactions_journal_test() {
+    let _ = fs::remove_dir_all(TREE_PATH);
+    let mut blockchain = BlockChainTree::without_config_at(TREE_PATH).unwrap();
+    let sender = address_from_secret_key(PRIVATE_KEY).unwrap();
+
+    blockchain
+        .add_funds(&sender, &1000u64.to_biguint().unwrap())
+        .await
+        .unwrap();
+
+    let mut tr = Transaction::new_versioned(
+        &sender,
+        RECIEVER,
+        121212,
+        SIGNATURE,
+        100u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        blockchain.get_nonce(&sender).unwrap(),
+    );
+    tr.sign(&[0u8; 32], PRIVATE_KEY).unwrap();
+    let hash = tr.get_id();
+    blockchain.new_transaction(tr).await.unwrap();
+
+    let replayed = PoolJournal::open(Path::new(POOL_PATH))
+        .unwrap()
+        .replay()
+        .unwrap();
+    assert!(replayed
+        .iter()
+        .any(|(_, transaction)| transaction.get_id() == hash));
+
+    // transactions taken out of the pool don't come back on restart
+    let popped = blockchain.pop_last_transactions().unwrap().unwrap();
+    assert!(popped
+        .iter()
+        .any(|transaction| transaction.get_id() == hash));
+
+    let replayed = PoolJournal::open(Path::new(POOL_PATH))
+        .unwrap()
+        .replay()
+        .unwrap();
+    assert!(!replayed
+        .iter()
+        .any(|(_, transaction)| transaction.get_id() == hash));
+
+    drop(blockchain);
+    let _ = fs::remove_dir_all(TREE_PATH);
+}
@@ -0,0 +1,204 @@
+use blockchaintree::block::{BasicInfo, TransactionBlock};
+use blockchaintree::blockchaintree::BlockChainTree;
+use blockchaintree::merkletree::{self, MerkleTree};
+use blockchaintree::transaction::{Transaction, Transactionable};
+use blockchaintree::wallet::address_from_secret_key;
+use num_bigint::ToBigUint;
+use std::fs;
+
+static RECIEVER: &[u8; 33] = b"123456789012345678901234567890123";
+static PRIVATE_KEY: &[u8; 32] = &[9u8; 32];
+static RECEIPT_PATH: &str = "./receipt_test_tree/";
+static MULTIPLE_RECEIPTS_PATH: &str = "./multiple_receipts_test_tree/";
+
+#[tokio::test]
+async fn receipt_test() {
+    let _ = fs::remove_dir_all(RECEIPT_PATH);
+    let mut blockchain = BlockChainTree::without_config_at(RECEIPT_PATH).unwrap();
+    let sender = address_from_secret_key(PRIVATE_KEY).unwrap();
+
+    blockchain
+        .add_funds(&sender, &1000u64.to_biguint().unwrap())
+        .await
+        .unwrap();
+
+    let prev_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+    let height = blockchain.get_main_chain().get_height();
+
+    let mut tr = Transaction::new_versioned(
+        &sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        blockchain.get_nonce(&sender).unwrap(),
+    );
+    tr.sign(&prev_hash, PRIVATE_KEY).unwrap();
+    let key = tr.get_id();
+    let dump = tr.dump().unwrap();
+
+    let mut merkle_tree = MerkleTree::new();
+    merkle_tree.add_objects(vec![&tr.get_id()]);
+    let merkle_tree_root = *merkle_tree.get_root();
+
+    let transactions: Vec<Box<dyn Transactionable>> = vec![Box::new(tr)];
+    let state_root = blockchain.calculate_state_root(&transactions).unwrap();
+
+    let info = BasicInfo::new(
+        121213,
+        0u64.to_biguint().unwrap(),
+        prev_hash,
+        [0u8; 32],
+        height,
+        [0xffu8; 32],
+        state_root,
+    );
+    let block = TransactionBlock::new(
+        transactions,
+        1u64.to_biguint().unwrap(),
+        info,
+        merkle_tree_root,
+    );
+    blockchain.add_transaction_block(block).await.unwrap();
+
+    let receipt = blockchain.get_receipt(&key).unwrap().unwrap();
+    assert_eq!(receipt.get_height(), height);
+    assert_eq!(receipt.get_position(), 0);
+    assert_eq!(receipt.get_confirmations(), 1);
+    assert_eq!(receipt.get_transaction().dump().unwrap(), dump);
+    assert!(receipt.verify_proof());
+
+    let transaction = blockchain.get_transaction(&key).unwrap().unwrap();
+    assert_eq!(transaction.dump().unwrap(), dump);
+
+    assert!(blockchain.get_receipt(&[0u8; 32]).unwrap().is_none());
+    assert!(blockchain.get_transaction(&[0u8; 32]).unwrap().is_none());
+
+    drop(blockchain);
+    let _ = fs::remove_dir_all(RECEIPT_PATH);
+}
+
+#[tokio::test]
+async fn multiple_transactions_receipt_test() {
+    let _ = fs::remove_dir_all(MULTIPLE_RECEIPTS_PATH);
+    let mut blockchain = BlockChainTree::without_config_at(MULTIPLE_RECEIPTS_PATH).unwrap();
+    let sender = address_from_secret_key(PRIVATE_KEY).unwrap();
+
+    blockchain
+        .add_funds(&sender, &1000u64.to_biguint().unwrap())
+        .await
+        .unwrap();
+
+    let prev_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+    let height = blockchain.get_main_chain().get_height();
+    let nonce = blockchain.get_nonce(&sender).unwrap();
+
+    let mut transactions: Vec<Box<dyn Transactionable>> = Vec::new();
+    for i in 0..3 {
+        let mut tr = Transaction::new_versioned(
+            &sender,
+            RECIEVER,
+            121212 + i,
+            &[0u8; 64],
+            100u64.to_biguint().unwrap(),
+            1u64.to_biguint().unwrap(),
+            nonce + i,
+        );
+        tr.sign(&prev_hash, PRIVATE_KEY).unwrap();
+        transactions.push(Box::new(tr));
+    }
+    let keys: Vec<[u8; 32]> = transactions.iter().map(|tr| tr.get_id()).collect();
+    let leaves: Vec<[u8; 32]> = transactions.iter().map(|tr| tr.get_id()).collect();
+
+    let mut merkle_tree = MerkleTree::new();
+    merkle_tree.add_objects(leaves.iter().collect());
+    let merkle_tree_root = *merkle_tree.get_root();
+
+    let state_root = blockchain.calculate_state_root(&transactions).unwrap();
+
+    let info = BasicInfo::new(
+        121213,
+        0u64.to_biguint().unwrap(),
+        prev_hash,
+        [0u8; 32],
+        height,
+        [0xffu8; 32],
+        state_root,
+    );
+    let block = TransactionBlock::new(
+        transactions,
+        3u64.to_biguint().unwrap(),
+        info,
+        merkle_tree_root,
+    );
+    blockchain.add_transaction_block(block).await.unwrap();
+
+    for (position, key) in keys.iter().enumerate() {
+        let receipt = blockchain.get_receipt(key).unwrap().unwrap();
+        assert_eq!(receipt.get_position(), position);
+        assert!(receipt.verify_proof());
+
+        let proof: Vec<&[u8; 32]> = receipt.get_proof().iter().collect();
+        // the proof doesn't hold for other positions or transactions
+        assert!(!merkletree::verify_proof(
+            &leaves[position],
+            &merkle_tree_root,
+            proof.clone(),
+            position ^ 1
+        ));
+        assert!(!merkletree::verify_proof(
+            &leaves[(position + 1) % leaves.len()],
+            &merkle_tree_root,
+            proof,
+            position
+        ));
+    }
+
+    drop(blockchain);
+    let _ = fs::remove_dir_all(MULTIPLE_RECEIPTS_PATH);
+}
+
+#[test]
+fn merkle_proof_test() {
+    for amount in 1..10u8 {
+        let leaves: Vec<[u8; 32]> = (0..amount).map(|i| [i; 32]).collect();
+
+        let mut merkle_tree = MerkleTree::new();
+        assert!(merkle_tree.add_objects(leaves.iter().collect()));
+        let root = *merkle_tree.get_root();
+
+        for (position, leaf) in leaves.iter().enumerate() {
+            let proof = merkle_tree.get_proof(leaf).unwrap();
+            assert!(merkletree::verify_proof(
+                leaf,
+                &root,
+                proof.clone(),
+                position
+            ));
+
+            // siblings are hashed in order
+            assert!(!merkletree::verify_proof(
+                leaf,
+                &root,
+                proof.clone(),
+                position ^ 1
+            ));
+
+            // the proof is bound to the leaf
+            assert!(!merkletree::verify_proof(
+                &[0xaa; 32],
+                &root,
+                proof,
+                position
+            ));
+        }
+    }
+
+    // swapping two leaves changes the root
+    let mut first = MerkleTree::new();
+    first.add_objects(vec![&[1u8; 32], &[2u8; 32]]);
+    let mut second = MerkleTree::new();
+    second.add_objects(vec![&[2u8; 32], &[1u8; 32]]);
+    assert_ne!(first.get_root(), second.get_root());
+}
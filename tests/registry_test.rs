@@ -0,0 +1,73 @@
+use blockchaintree::dump_headers::Headers;
+use blockchaintree::errors::TransactionError;
+use blockchaintree::pool_journal::parse_transaction;
+use blockchaintree::registry::{self, TransactionRegistry};
+use blockchaintree::transaction::{Transaction, Transactionable};
+use num_bigint::ToBigUint;
+
+use error_stack::Result;
+
+static SENDER: &[u8; 33] = b"123456789012345678901234567890123";
+static RECIEVER: &[u8; 33] = b"000000000000000000000000000000000";
+
+static CUSTOM_HEADER: u8 = 200;
+
+fn transaction() -> Transaction {
+    Transaction::new_versioned(
+        SENDER,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+        10u64.to_biguint().unwrap(),
+        3,
+    )
+}
+
+// kind defined outside of the crate, stored as a versioned transaction
+fn parse_custom(data: &[u8], size: u64) -> Result<Box<dyn Transactionable>, TransactionError> {
+    Ok(Box::new(Transaction::parse_versioned(data, size)?))
+}
+
+#[test]
+fn registry_test() {
+    let mut registry = TransactionRegistry::default();
+    let dump = transaction().dump().unwrap();
+
+    let parsed = registry.parse(&dump).unwrap();
+    assert_eq!(parsed.get_id(), transaction().get_id());
+
+    // kinds of the crate are registered by default
+    assert!(registry.is_registered(Headers::Token as u8));
+
+    // unknown kinds have no parsers
+    assert!(!registry.is_registered(CUSTOM_HEADER));
+    let mut custom_dump = dump.clone();
+    custom_dump[0] = CUSTOM_HEADER;
+    assert!(registry.parse(&custom_dump).is_err());
+    assert!(registry.parse(&[]).is_err());
+
+    // headers of registered kinds can't be taken
+    assert!(registry
+        .register(Headers::VersionedTransaction as u8, parse_custom)
+        .is_err());
+
+    registry.register(CUSTOM_HEADER, parse_custom).unwrap();
+    let parsed = registry.parse(&custom_dump).unwrap();
+    assert_eq!(parsed.get_nonce(), Some(3));
+
+    assert!(TransactionRegistry::new().parse(&dump).is_err());
+}
+
+#[test]
+fn shared_registry_test() {
+    let mut custom_dump = transaction().dump().unwrap();
+    custom_dump[0] = CUSTOM_HEADER + 1;
+    assert!(parse_transaction(&custom_dump).is_err());
+
+    // registered kinds are parsed by the pool journal and blocks
+    registry::register(CUSTOM_HEADER + 1, parse_custom).unwrap();
+    assert!(registry::is_registered(CUSTOM_HEADER + 1));
+    let parsed = parse_transaction(&custom_dump).unwrap();
+    assert_eq!(parsed.get_id(), transaction().get_id());
+}
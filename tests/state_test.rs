@@ -0,0 +1,94 @@
+use blockchaintree::state::{state_leaf, Account, StateTree, MAX_BALANCE_BYTES};
+use num_bigint::{BigUint, ToBigUint};
+
+static FIRST: &[u8; 33] = b"123456789012345678901234567890123";
+static SECOND: &[u8; 33] = b"abcdefghijklmnopqrstuvwxyzabcdefg";
+static THIRD: &[u8; 33] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZABCDEFG";
+
+fn account(balance: u64, nonce: u64) -> Account {
+    Account::new(balance.to_biguint().unwrap(), nonce)
+}
+
+fn storage() -> sled::Db {
+    sled::Config::new().temporary(true).open().unwrap()
+}
+
+fn commit(db: &sled::Db, tree: &StateTree) {
+    let mut batch = sled::Batch::default();
+    tree.write(&mut batch);
+    db.apply_batch(batch).unwrap();
+}
+
+#[test]
+fn state_root_test() {
+    let empty_db = storage();
+    let empty_root = StateTree::new(&empty_db).root().unwrap();
+
+    // order of updates doesn't matter
+    let first_db = storage();
+    let mut first = StateTree::new(&first_db);
+    first.insert(FIRST, &account(100, 0)).unwrap();
+    first.insert(SECOND, &account(50, 0)).unwrap();
+    first.insert(THIRD, &account(10, 3)).unwrap();
+
+    let second_db = storage();
+    let mut second = StateTree::new(&second_db);
+    second.insert(THIRD, &account(10, 3)).unwrap();
+    second.insert(SECOND, &account(50, 0)).unwrap();
+    second.insert(FIRST, &account(100, 0)).unwrap();
+
+    let root = first.root().unwrap();
+    assert_eq!(root, second.root().unwrap());
+    assert_ne!(root, empty_root);
+
+    // stored nodes give the same root
+    commit(&first_db, &first);
+    assert_eq!(StateTree::new(&first_db).root().unwrap(), root);
+
+    // updating stored tree equals building from scratch
+    let mut updated = StateTree::new(&first_db);
+    updated.insert(FIRST, &account(70, 1)).unwrap();
+    updated.insert(SECOND, &account(80, 0)).unwrap();
+
+    let rebuilt_db = storage();
+    let mut rebuilt = StateTree::new(&rebuilt_db);
+    rebuilt.insert(FIRST, &account(70, 1)).unwrap();
+    rebuilt.insert(SECOND, &account(80, 0)).unwrap();
+    rebuilt.insert(THIRD, &account(10, 3)).unwrap();
+    assert_eq!(updated.root().unwrap(), rebuilt.root().unwrap());
+
+    // uncommitted changes don't touch the storage
+    assert_eq!(StateTree::new(&first_db).root().unwrap(), root);
+
+    // nonce is a part of the state
+    let mut other_nonce = StateTree::new(&first_db);
+    other_nonce.insert(FIRST, &account(70, 2)).unwrap();
+    other_nonce.insert(SECOND, &account(80, 0)).unwrap();
+    assert_ne!(updated.root().unwrap(), other_nonce.root().unwrap());
+
+    // balances can't be moved between accounts keeping the root
+    let mut moved = StateTree::new(&first_db);
+    moved.insert(FIRST, &account(80, 1)).unwrap();
+    moved.insert(SECOND, &account(70, 0)).unwrap();
+    assert_ne!(updated.root().unwrap(), moved.root().unwrap());
+
+    // empty accounts are dropped from the tree and its storage
+    let mut emptied = StateTree::new(&first_db);
+    emptied.insert(FIRST, &account(0, 0)).unwrap();
+    emptied.insert(SECOND, &account(0, 0)).unwrap();
+    emptied.insert(THIRD, &account(0, 0)).unwrap();
+    assert_eq!(emptied.root().unwrap(), empty_root);
+    commit(&first_db, &emptied);
+    assert!(first_db.is_empty());
+}
+
+#[test]
+fn large_balance_test() {
+    let largest = (BigUint::from(1u8) << (MAX_BALANCE_BYTES * 8)) - 1u8;
+    assert!(state_leaf(FIRST, &Account::new(largest.clone(), 0)).is_ok());
+
+    // balance can't be dumped into the summary db
+    let db = storage();
+    let mut tree = StateTree::new(&db);
+    assert!(tree.insert(FIRST, &Account::new(largest + 1u8, 0)).is_err());
+}
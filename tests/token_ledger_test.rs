@@ -0,0 +1,117 @@
+use blockchaintree::token::{Action, Token, TokenAction};
+use blockchaintree::token_ledger::{apply_action, issue_token, TokenLedger, TokenState};
+use blockchaintree::transaction::Transactionable;
+use num_bigint::BigUint;
+use num_traits::Zero;
+use std::fs;
+use std::path::Path;
+
+static OWNER: &[u8; 33] = b"123456789012345678901234567890123";
+static RECIEVER: &[u8; 33] = b"abcdefghijklmnopqrstuvwxyzabcdefg";
+static TOKEN_HASH: [u8; 32] = [1u8; 32];
+static LEDGER_PATH: &str = "./token_ledger_test/";
+
+fn token() -> Token {
+    Token::new(
+        *OWNER,
+        [0u8; 64],
+        TOKEN_HASH,
+        String::new(),
+        String::new(),
+        BigUint::zero(),
+        BigUint::zero(),
+        false,
+    )
+    .unwrap()
+}
+
+fn action(action: Action, from: &[u8; 33], to: &[u8; 33], nonce: u64) -> TokenAction {
+    TokenAction::new(action, to, from, &[0u8; 64], TOKEN_HASH, 121212, nonce)
+}
+
+#[test]
+fn token_state_test() {
+    let state = issue_token(None, &token()).unwrap();
+    assert_eq!(state, TokenState::new(*OWNER, false, false));
+    assert_eq!(TokenState::parse(&state.dump()).unwrap(), state);
+    // token hash is taken
+    assert!(issue_token(Some(&state), &token()).is_err());
+
+    // only the owner can send
+    assert!(apply_action(None, &action(Action::Send, OWNER, RECIEVER, 0)).is_err());
+    assert!(apply_action(Some(&state), &action(Action::Send, RECIEVER, OWNER, 0)).is_err());
+    let sent = apply_action(Some(&state), &action(Action::Send, OWNER, RECIEVER, 0)).unwrap();
+    assert_eq!(sent.get_owner(), RECIEVER);
+
+    // only unassigned tokens can be assigned
+    let assigned =
+        apply_action(Some(&sent), &action(Action::Assign, RECIEVER, RECIEVER, 0)).unwrap();
+    assert!(assigned.is_assigned());
+    assert!(apply_action(
+        Some(&assigned),
+        &action(Action::Assign, RECIEVER, RECIEVER, 1)
+    )
+    .is_err());
+
+    // burned tokens can't be changed
+    let burned = apply_action(
+        Some(&assigned),
+        &action(Action::Burn, RECIEVER, RECIEVER, 1),
+    )
+    .unwrap();
+    assert!(burned.is_burned());
+    assert_eq!(TokenState::parse(&burned.dump()).unwrap(), burned);
+    for kind in [Action::Send, Action::Assign, Action::Burn] {
+        assert!(apply_action(Some(&burned), &action(kind, RECIEVER, OWNER, 2)).is_err());
+    }
+}
+
+#[tokio::test]
+async fn token_ledger_test() {
+    let path = Path::new(LEDGER_PATH);
+    let _ = fs::remove_dir_all(path);
+
+    let ledger = TokenLedger::open(path).unwrap();
+    assert!(ledger.get(&TOKEN_HASH).unwrap().is_none());
+
+    // actions on unknown tokens are rejected
+    let transactions: Vec<Box<dyn Transactionable>> =
+        vec![Box::new(action(Action::Send, OWNER, RECIEVER, 0))];
+    assert!(ledger.calculate_changes(&transactions).is_err());
+
+    // transactions see changes of the previous ones
+    let transactions: Vec<Box<dyn Transactionable>> = vec![
+        Box::new(token()),
+        Box::new(action(Action::Send, OWNER, RECIEVER, 0)),
+        Box::new(action(Action::Assign, RECIEVER, RECIEVER, 0)),
+    ];
+    let changes = ledger.calculate_changes(&transactions).unwrap();
+    assert_eq!(
+        changes[&TOKEN_HASH],
+        (None, TokenState::new(*RECIEVER, true, false))
+    );
+    ledger.apply(&changes).await.unwrap();
+
+    assert_eq!(
+        ledger.get(&TOKEN_HASH).unwrap(),
+        Some(TokenState::new(*RECIEVER, true, false))
+    );
+    assert!(ledger.get_by_owner(OWNER).unwrap().is_empty());
+    assert_eq!(ledger.get_by_owner(RECIEVER).unwrap(), vec![TOKEN_HASH]);
+
+    // previous owner can't act anymore
+    let transactions: Vec<Box<dyn Transactionable>> =
+        vec![Box::new(action(Action::Send, OWNER, OWNER, 1))];
+    assert!(ledger.calculate_changes(&transactions).is_err());
+
+    // burned tokens don't belong to anyone
+    let transactions: Vec<Box<dyn Transactionable>> =
+        vec![Box::new(action(Action::Burn, RECIEVER, RECIEVER, 1))];
+    let changes = ledger.calculate_changes(&transactions).unwrap();
+    ledger.apply(&changes).await.unwrap();
+    assert!(ledger.get(&TOKEN_HASH).unwrap().unwrap().is_burned());
+    assert!(ledger.get_by_owner(RECIEVER).unwrap().is_empty());
+
+    drop(ledger);
+    fs::remove_dir_all(path).unwrap();
+}
@@ -0,0 +1,354 @@
+use blockchaintree::block::{BasicInfo, TransactionBlock};
+use blockchaintree::blockchaintree::BlockChainTree;
+use blockchaintree::dump_headers::Headers;
+use blockchaintree::history::HistoryCursor;
+use blockchaintree::merkletree::MerkleTree;
+use blockchaintree::pool_journal::parse_transaction;
+use blockchaintree::token::{Action, Token, TokenAction};
+use blockchaintree::tools;
+use blockchaintree::transaction::Transactionable;
+use blockchaintree::wallet::address_from_secret_key;
+use num_bigint::{BigUint, ToBigUint};
+use num_traits::Zero;
+use std::fs;
+
+static OWNER: [u8; 33] = *b"123456789012345678901234567890123";
+static PRIVATE_KEY: &[u8; 32] = &[7u8; 32];
+static TREE_PATH: &str = "./token_fee_test_tree/";
+/// tokens aren't bound to the previous block
+static PREV_HASH: &[u8; 32] = &[0u8; 32];
+
+#[test]
+fn token_creation_test() {
+    let unassigned = |token_data: &str, coin_supply: u64, transfer_fee: u64| {
+        Token::new(
+            OWNER,
+            [0u8; 64],
+            [1u8; 32],
+            token_data.to_string(),
+            String::new(),
+            coin_supply.to_biguint().unwrap(),
+            transfer_fee.to_biguint().unwrap(),
+            false,
+        )
+    };
+
+    // unassigned token carries no data
+    assert!(unassigned("", 0, 0).is_ok());
+    assert!(unassigned("data", 0, 0).is_err());
+    assert!(unassigned("", 100, 0).is_err());
+    assert!(unassigned("", 0, 1).is_err());
+
+    let token = Token::new(
+        OWNER,
+        [0u8; 64],
+        [1u8; 32],
+        "data".to_string(),
+        "contract".to_string(),
+        BigUint::zero(),
+        10u64.to_biguint().unwrap(),
+        true,
+    )
+    .unwrap();
+    assert!(token.is_fee_static());
+    assert!(!unassigned("", 0, 0).unwrap().is_fee_static());
+}
+
+#[test]
+fn token_dump_test() {
+    let owner = address_from_secret_key(PRIVATE_KEY).unwrap();
+
+    let mut assigned = Token::new(
+        owner,
+        [0u8; 64],
+        [1u8; 32],
+        "data".to_string(),
+        "contract".to_string(),
+        1000u64.to_biguint().unwrap(),
+        10u64.to_biguint().unwrap(),
+        true,
+    )
+    .unwrap()
+    .with_nonce(3)
+    .with_fee(5u64.to_biguint().unwrap())
+    .unwrap();
+    let id = Transactionable::get_id(&assigned);
+    assigned.sign(PREV_HASH, PRIVATE_KEY).unwrap();
+    assert!(assigned.verify(PREV_HASH).unwrap());
+
+    // owner pays the fee with the nonce, the id doesn't depend on the signature
+    assert_eq!(Transactionable::get_nonce(&assigned), Some(3));
+    assert_eq!(
+        Transactionable::get_fee(&assigned),
+        Some(&5u64.to_biguint().unwrap())
+    );
+    assert_eq!(Transactionable::get_id(&assigned), id);
+
+    // moving bytes between the fields breaks the signature
+    let shifted = Token::new(
+        owner,
+        *assigned.get_signature(),
+        [1u8; 32],
+        "datac".to_string(),
+        "ontract".to_string(),
+        1000u64.to_biguint().unwrap(),
+        10u64.to_biguint().unwrap(),
+        true,
+    )
+    .unwrap();
+    assert!(!shifted.verify(PREV_HASH).unwrap());
+
+    let unassigned = Token::new(
+        OWNER,
+        [0u8; 64],
+        [2u8; 32],
+        String::new(),
+        String::new(),
+        BigUint::zero(),
+        BigUint::zero(),
+        false,
+    )
+    .unwrap();
+
+    for token in [&assigned, &unassigned] {
+        let dump = token.dump().unwrap();
+        assert_eq!(dump[0], Headers::Token as u8);
+        assert_eq!(dump.len(), token.get_dump_size());
+
+        let parsed = Token::parse(&dump[1..], (dump.len() - 1) as u64).unwrap();
+        assert_eq!(parsed.dump().unwrap(), dump);
+        assert_eq!(parsed.get_id(), token.get_id());
+        assert_eq!(parsed.hash(PREV_HASH), token.hash(PREV_HASH));
+        assert_eq!(parsed.is_assigned(), token.is_assigned());
+
+        // size has to match
+        assert!(Token::parse(&dump[1..], dump.len() as u64).is_err());
+        assert!(Token::parse(&dump[1..dump.len() - 1], (dump.len() - 2) as u64).is_err());
+    }
+    let parsed = Token::parse(
+        &assigned.dump().unwrap()[1..],
+        (assigned.get_dump_size() - 1) as u64,
+    )
+    .unwrap();
+    assert_eq!(parsed.get_token_data(), "data");
+    assert_eq!(parsed.get_smol_contract(), "contract");
+    assert_eq!(parsed.get_coin_supply(), &1000u64.to_biguint().unwrap());
+    assert_eq!(parsed.get_nonce(), Some(3));
+    assert_eq!(parsed.get_fee(), Some(&5u64.to_biguint().unwrap()));
+    assert!(parsed.verify(PREV_HASH).unwrap());
+
+    // fee is signed
+    let mut dump = assigned.dump().unwrap();
+    dump[1 + 33 + 32 + 64 + 8 + 1] = 6;
+    let tampered = Token::parse(&dump[1..], (dump.len() - 1) as u64).unwrap();
+    assert!(!tampered.verify(PREV_HASH).unwrap());
+
+    // tokens are parsed inside transaction blocks
+    let mut merkle_tree = MerkleTree::new();
+    merkle_tree.add_objects(vec![&assigned.get_id()]);
+    let merkle_tree_root = *merkle_tree.get_root();
+    let info = BasicInfo::new(
+        121213,
+        0u64.to_biguint().unwrap(),
+        [0u8; 32],
+        [0u8; 32],
+        1,
+        [0xffu8; 32],
+        [0u8; 32],
+    );
+    let block = TransactionBlock::new(
+        vec![Box::new(assigned)],
+        0u64.to_biguint().unwrap(),
+        info,
+        merkle_tree_root,
+    );
+    let dump = block.dump().unwrap();
+    let parsed = TransactionBlock::parse(&dump[1..], (dump.len() - 1) as u32).unwrap();
+    assert_eq!(parsed.get_transactions().len(), 1);
+    assert_eq!(
+        parsed.get_transactions()[0].dump().unwrap(),
+        block.get_transactions()[0].dump().unwrap()
+    );
+}
+
+#[test]
+fn token_action_test() {
+    let owner = address_from_secret_key(PRIVATE_KEY).unwrap();
+    let prev_hash = [3u8; 32];
+
+    for action in [Action::Send, Action::Assign, Action::Burn] {
+        let mut token_action =
+            TokenAction::new(action, &OWNER, &owner, &[0u8; 64], [1u8; 32], 121212, 4);
+        assert!(!token_action.verify(&prev_hash).unwrap());
+        token_action.sign(&prev_hash, PRIVATE_KEY).unwrap();
+        assert!(token_action.verify(&prev_hash).unwrap());
+
+        let dump = token_action.dump().unwrap();
+        assert_eq!(dump[0], Headers::TokenAction as u8);
+        assert_eq!(dump.len(), token_action.get_dump_size());
+
+        let parsed = parse_transaction(&dump).unwrap();
+        assert_eq!(parsed.get_id(), token_action.get_id());
+        assert_eq!(parsed.get_sender(), &owner);
+        // burned token has no owner to receive it
+        match action {
+            Action::Burn => assert_eq!(parsed.get_receiver(), &owner),
+            _ => assert_eq!(parsed.get_receiver(), &OWNER),
+        }
+        assert_eq!(parsed.get_nonce(), Some(4));
+        assert!(parsed.verify(&prev_hash).unwrap());
+    }
+
+    // signature covers the action and the fee
+    let mut send = TokenAction::new(Action::Send, &OWNER, &owner, &[0u8; 64], [1u8; 32], 1, 0)
+        .with_fee(7u64.to_biguint().unwrap())
+        .unwrap();
+    send.sign(&prev_hash, PRIVATE_KEY).unwrap();
+    let mut dump = send.dump().unwrap();
+    let parsed = parse_transaction(&dump).unwrap();
+    assert_eq!(parsed.get_fee(), Some(&7u64.to_biguint().unwrap()));
+    assert!(parsed.verify(&prev_hash).unwrap());
+
+    dump[1 + 1 + 33 + 33 + 32 + 8 + 8 + 1] = 1;
+    assert!(!parse_transaction(&dump)
+        .unwrap()
+        .verify(&prev_hash)
+        .unwrap());
+    dump[1 + 1 + 33 + 33 + 32 + 8 + 8 + 1] = 7;
+
+    let mut dump = send.dump().unwrap();
+    dump[1] = Action::Burn as u8;
+    assert!(!parse_transaction(&dump)
+        .unwrap()
+        .verify(&prev_hash)
+        .unwrap());
+
+    // unknown actions and wrong sizes are rejected
+    dump[1] = 0;
+    assert!(parse_transaction(&dump).is_err());
+    assert!(TokenAction::parse(&dump[1..dump.len() - 1], (dump.len() - 2) as u64).is_err());
+}
+
+fn token_block(
+    blockchain: &mut BlockChainTree,
+    transactions: Vec<Box<dyn Transactionable>>,
+) -> TransactionBlock {
+    let prev_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+    let height = blockchain.get_main_chain().get_height();
+
+    let hashes: Vec<[u8; 32]> = transactions
+        .iter()
+        .map(|transaction| transaction.get_id())
+        .collect();
+    let mut merkle_tree = MerkleTree::new();
+    merkle_tree.add_objects(hashes.iter().collect());
+    let merkle_tree_root = *merkle_tree.get_root();
+
+    let state_root = blockchain.calculate_state_root(&transactions).unwrap();
+
+    let info = BasicInfo::new(
+        121213,
+        0u64.to_biguint().unwrap(),
+        prev_hash,
+        [0u8; 32],
+        height,
+        [0xffu8; 32],
+        state_root,
+    );
+    TransactionBlock::new(
+        transactions,
+        5u64.to_biguint().unwrap(),
+        info,
+        merkle_tree_root,
+    )
+}
+
+#[tokio::test]
+async fn token_fee_test() {
+    let _ = fs::remove_dir_all(TREE_PATH);
+    let mut blockchain = BlockChainTree::without_config_at(TREE_PATH).unwrap();
+    let owner = address_from_secret_key(PRIVATE_KEY).unwrap();
+
+    blockchain
+        .add_funds(&owner, &100u64.to_biguint().unwrap())
+        .await
+        .unwrap();
+    let funds = blockchain.get_funds(&owner).unwrap();
+    let nonce = blockchain.get_nonce(&owner).unwrap();
+
+    let token = |nonce: u64| {
+        let mut token = Token::new(
+            owner,
+            [0u8; 64],
+            tools::hash(&nonce.to_be_bytes()),
+            String::new(),
+            String::new(),
+            BigUint::zero(),
+            BigUint::zero(),
+            false,
+        )
+        .unwrap()
+        .with_nonce(nonce)
+        .with_fee(5u64.to_biguint().unwrap())
+        .unwrap();
+        token.sign(PREV_HASH, PRIVATE_KEY).unwrap();
+        token
+    };
+
+    // tokens use the nonce of the owner like transfers
+    assert!(blockchain
+        .new_transactionable(Box::new(token(nonce + 1)))
+        .await
+        .is_err());
+
+    let token = token(nonce);
+    let token_hash = *token.get_token_hash();
+    let block = token_block(&mut blockchain, vec![Box::new(token)]);
+    blockchain.add_transaction_block(block).await.unwrap();
+
+    // the owner paid the fee
+    assert_eq!(
+        blockchain.get_funds(&owner).unwrap(),
+        funds - 5u64.to_biguint().unwrap()
+    );
+    assert_eq!(blockchain.get_nonce(&owner).unwrap(), nonce + 1);
+
+    // burn ignores the current owner, it isn't recorded anywhere
+    let prev_hash = blockchain.get_main_chain().get_last_hash().unwrap();
+    let mut burn = TokenAction::new(
+        Action::Burn,
+        &OWNER,
+        &owner,
+        &[0u8; 64],
+        token_hash,
+        121214,
+        nonce + 1,
+    )
+    .with_fee(5u64.to_biguint().unwrap())
+    .unwrap();
+    burn.sign(&prev_hash, PRIVATE_KEY).unwrap();
+    let block = token_block(&mut blockchain, vec![Box::new(burn)]);
+    blockchain.add_transaction_block(block).await.unwrap();
+
+    assert!(blockchain
+        .get_token_state(&token_hash)
+        .unwrap()
+        .unwrap()
+        .is_burned());
+    assert!(blockchain
+        .address_history(&OWNER, HistoryCursor::default(), 10)
+        .unwrap()
+        .get_entries()
+        .is_empty());
+    assert_eq!(
+        blockchain
+            .address_history(&owner, HistoryCursor::default(), 10)
+            .unwrap()
+            .get_entries()
+            .len(),
+        2
+    );
+
+    drop(blockchain);
+    let _ = fs::remove_dir_all(TREE_PATH);
+}
@@ -0,0 +1,274 @@
+use blockchaintree::transaction::{
+    is_expired, is_nonce_required, is_unlocked, SignatureScheme, Transaction, Transactionable,
+    LATEST_VERSION, LOCK_TIME_THRESHOLD, NONCE_ACTIVATION_HEIGHT,
+};
+use num_bigint::ToBigUint;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+static RECIEVER: &[u8; 33] = b"123456789012345678901234567890123";
+static PREV_HASH: &[u8; 32] = b"12345678901234567890123456789012";
+static PRIVATE_KEY: &[u8; 32] = &[7u8; 32];
+
+fn sender() -> [u8; 33] {
+    let secret_key = SecretKey::from_slice(PRIVATE_KEY).unwrap();
+    PublicKey::from_secret_key(&Secp256k1::new(), &secret_key).serialize()
+}
+
+#[test]
+fn versioned_transaction_test() {
+    let sender = sender();
+
+    let mut tr = Transaction::new_versioned(
+        &sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        2222222288u64.to_biguint().unwrap(),
+        1000u64.to_biguint().unwrap(),
+        5,
+    );
+    tr.sign(PREV_HASH, PRIVATE_KEY).unwrap();
+
+    assert!(tr.verify(PREV_HASH).unwrap());
+    // nonce replaces binding to the previous block
+    assert!(tr.verify(&[0u8; 32]).unwrap());
+
+    let dump = tr.dump().unwrap();
+    assert_eq!(dump.len(), tr.get_dump_size());
+
+    let parsed = Transaction::parse_versioned(&dump[1..], (dump.len() - 1) as u64).unwrap();
+    assert_eq!(parsed.get_nonce(), Some(5));
+    assert_eq!(parsed.get_fee(), Some(&1000u64.to_biguint().unwrap()));
+    assert_eq!(parsed.get_sender(), &sender);
+    assert_eq!(parsed.hash(PREV_HASH), tr.hash(PREV_HASH));
+    assert!(parsed.verify(PREV_HASH).unwrap());
+
+    // fee is signed
+    let mut tampered = Transaction::new_versioned(
+        &sender,
+        RECIEVER,
+        121212,
+        tr.get_signature(),
+        2222222288u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        5,
+    );
+    assert!(!tampered.verify(PREV_HASH).unwrap());
+    tampered.sign(PREV_HASH, PRIVATE_KEY).unwrap();
+    assert!(tampered.verify(PREV_HASH).unwrap());
+}
+
+#[test]
+fn truncated_versioned_transaction_test() {
+    let sender = sender();
+
+    let mut tr = Transaction::new_versioned(
+        &sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        2222222288u64.to_biguint().unwrap(),
+        1000u64.to_biguint().unwrap(),
+        5,
+    );
+    tr.sign(PREV_HASH, PRIVATE_KEY).unwrap();
+    let dump = tr.dump().unwrap();
+
+    // cut right after the amount: fee, lock time, scheme and expiry height are missing
+    let truncated = &dump[1..dump.len() - 3 - 8 - 1 - 8];
+    assert!(Transaction::parse_versioned(truncated, truncated.len() as u64).is_err());
+
+    for end in 2..dump.len() {
+        let truncated = &dump[1..end];
+        assert!(Transaction::parse_versioned(truncated, truncated.len() as u64).is_err());
+    }
+}
+
+#[test]
+fn locked_transaction_test() {
+    let sender = sender();
+
+    let mut tr = Transaction::new_versioned(
+        &sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        0,
+    )
+    .with_lock_time(1000);
+    tr.sign(PREV_HASH, PRIVATE_KEY).unwrap();
+    assert!(tr.verify(PREV_HASH).unwrap());
+    assert_eq!(tr.get_lock_time(), Some(1000));
+
+    let dump = tr.dump().unwrap();
+    assert_eq!(dump.len(), tr.get_dump_size());
+    let parsed = Transaction::parse_versioned(&dump[1..], (dump.len() - 1) as u64).unwrap();
+    assert_eq!(parsed.get_lock_time(), Some(1000));
+    assert!(parsed.verify(PREV_HASH).unwrap());
+
+    // lock is signed
+    let moved = Transaction::new_versioned(
+        &sender,
+        RECIEVER,
+        121212,
+        tr.get_signature(),
+        100u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        0,
+    );
+    assert!(!moved.verify(PREV_HASH).unwrap());
+
+    // heights below the threshold, timestamps above it
+    assert!(!is_unlocked(1000, 999, u64::MAX));
+    assert!(is_unlocked(1000, 1000, 0));
+    assert!(!is_unlocked(
+        LOCK_TIME_THRESHOLD + 10,
+        u64::MAX,
+        LOCK_TIME_THRESHOLD + 9
+    ));
+    assert!(is_unlocked(
+        LOCK_TIME_THRESHOLD + 10,
+        0,
+        LOCK_TIME_THRESHOLD + 10
+    ));
+}
+
+#[test]
+fn schnorr_transaction_test() {
+    let sender = sender();
+    let schnorr = |signature: &[u8; 64]| {
+        Transaction::new_versioned(
+            &sender,
+            RECIEVER,
+            121212,
+            signature,
+            100u64.to_biguint().unwrap(),
+            1u64.to_biguint().unwrap(),
+            0,
+        )
+        .with_scheme(SignatureScheme::Schnorr)
+    };
+
+    let mut tr = schnorr(&[0u8; 64]);
+    tr.sign(PREV_HASH, PRIVATE_KEY).unwrap();
+    assert!(tr.verify(PREV_HASH).unwrap());
+
+    let dump = tr.dump().unwrap();
+    assert_eq!(dump.len(), tr.get_dump_size());
+    let parsed = Transaction::parse_versioned(&dump[1..], (dump.len() - 1) as u64).unwrap();
+    assert_eq!(parsed.get_signature_scheme(), SignatureScheme::Schnorr);
+    assert!(parsed.verify(PREV_HASH).unwrap());
+
+    // scheme is signed, so the signature doesn't verify under the other one
+    let mut ecdsa = Transaction::new_versioned(
+        &sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        0,
+    );
+    assert_eq!(ecdsa.get_signature_scheme(), SignatureScheme::Ecdsa);
+    ecdsa.sign(PREV_HASH, PRIVATE_KEY).unwrap();
+    assert!(ecdsa.verify(PREV_HASH).unwrap());
+    assert!(!schnorr(ecdsa.get_signature())
+        .verify(PREV_HASH)
+        .unwrap_or(false));
+
+    // unknown scheme, it's followed by the expiry height
+    let mut wrong = dump.clone();
+    let scheme = wrong.len() - 8 - 1;
+    wrong[scheme] = 7;
+    assert!(Transaction::parse_versioned(&wrong[1..], (wrong.len() - 1) as u64).is_err());
+}
+
+#[test]
+fn transaction_id_test() {
+    let sender = sender();
+
+    let mut legacy = Transaction::new(
+        &sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+    );
+    legacy.sign(PREV_HASH, PRIVATE_KEY).unwrap();
+    let id = legacy.get_id();
+
+    // hash binds the transaction to the block, id doesn't
+    assert_ne!(legacy.hash(PREV_HASH), legacy.hash(&[0u8; 32]));
+    legacy.sign(&[0u8; 32], PRIVATE_KEY).unwrap();
+    assert_eq!(legacy.get_id(), id);
+
+    let versioned = Transaction::new_versioned(
+        &sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        0,
+    );
+    assert_ne!(versioned.get_id(), id);
+    assert_eq!(versioned.get_version(), LATEST_VERSION);
+
+    let dump = versioned.dump().unwrap();
+    let parsed = Transaction::parse_versioned(&dump[1..], (dump.len() - 1) as u64).unwrap();
+    assert_eq!(parsed.get_id(), versioned.get_id());
+}
+
+#[test]
+fn expiring_transaction_test() {
+    let sender = sender();
+    let expiring = |signature: &[u8; 64], expiry_height: u64| {
+        Transaction::new_versioned(
+            &sender,
+            RECIEVER,
+            121212,
+            signature,
+            100u64.to_biguint().unwrap(),
+            1u64.to_biguint().unwrap(),
+            0,
+        )
+        .with_expiry_height(expiry_height)
+    };
+
+    let mut tr = expiring(&[0u8; 64], 50);
+    tr.sign(PREV_HASH, PRIVATE_KEY).unwrap();
+    assert!(tr.verify(PREV_HASH).unwrap());
+    assert_eq!(tr.get_expiry_height(), Some(50));
+
+    let dump = tr.dump().unwrap();
+    assert_eq!(dump.len(), tr.get_dump_size());
+    let parsed = Transaction::parse_versioned(&dump[1..], (dump.len() - 1) as u64).unwrap();
+    assert_eq!(parsed.get_expiry_height(), Some(50));
+    assert!(parsed.verify(PREV_HASH).unwrap());
+
+    // expiry height is signed
+    assert!(!expiring(tr.get_signature(), 60).verify(PREV_HASH).unwrap());
+
+    assert!(!is_expired(50, 50));
+    assert!(is_expired(50, 51));
+}
+
+#[test]
+fn nonce_activation_test() {
+    // legacy transactions without a nonce are only accepted before the activation height
+    assert!(!is_nonce_required(0));
+    assert!(!is_nonce_required(NONCE_ACTIVATION_HEIGHT - 1));
+    assert!(is_nonce_required(NONCE_ACTIVATION_HEIGHT));
+
+    let legacy = Transaction::new(
+        &sender(),
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+    );
+    assert!(legacy.is_legacy());
+    assert_eq!(legacy.get_nonce(), None);
+}
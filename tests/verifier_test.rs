@@ -0,0 +1,54 @@
+use blockchaintree::transaction::{Transaction, Transactionable};
+use blockchaintree::verifier::BatchVerifier;
+use num_bigint::ToBigUint;
+
+static RECIEVER: &[u8; 33] = b"123456789012345678901234567890123";
+static PREV_HASH: &[u8; 32] = b"12345678901234567890123456789012";
+static PRIVATE_KEY: &[u8; 32] = &[7u8; 32];
+
+fn signed_transaction(sender: &[u8; 33], nonce: u64) -> Box<dyn Transactionable> {
+    let mut tr = Transaction::new_versioned(
+        sender,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        nonce,
+    );
+    tr.sign(PREV_HASH, PRIVATE_KEY).unwrap();
+    Box::new(tr)
+}
+
+#[test]
+fn verifier_test() {
+    let sender = blockchaintree::wallet::address_from_secret_key(PRIVATE_KEY).unwrap();
+    let mut verifier = BatchVerifier::new(4, 1000);
+
+    let mut transactions: Vec<Box<dyn Transactionable>> = (0..200)
+        .map(|nonce| signed_transaction(&sender, nonce))
+        .collect();
+    assert!(verifier.verify_batch(&transactions, PREV_HASH).unwrap());
+    assert!(verifier.is_verified(&transactions[150].hash(PREV_HASH)));
+
+    // single wrong signature fails the whole batch and isn't cached
+    let forged: Box<dyn Transactionable> = Box::new(Transaction::new_versioned(
+        &sender,
+        RECIEVER,
+        121212,
+        transactions[0].get_signature(),
+        1000u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        0,
+    ));
+    let forged_hash = forged.hash(PREV_HASH);
+    transactions.push(forged);
+    assert!(!verifier.verify_batch(&transactions, PREV_HASH).unwrap());
+    assert!(!verifier.is_verified(&forged_hash));
+
+    // cache is bounded
+    let mut small = BatchVerifier::new(1, 10);
+    assert!(small.verify_batch(&transactions[..20], PREV_HASH).unwrap());
+    assert!(!small.is_verified(&transactions[0].hash(PREV_HASH)));
+    assert!(small.is_verified(&transactions[19].hash(PREV_HASH)));
+}
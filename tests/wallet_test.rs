@@ -0,0 +1,57 @@
+use blockchaintree::transaction::{Transaction, Transactionable};
+use blockchaintree::wallet::{address_from_secret_key, Wallet};
+use num_bigint::ToBigUint;
+
+static RECIEVER: &[u8; 33] = b"123456789012345678901234567890123";
+static PREV_HASH: &[u8; 32] = b"12345678901234567890123456789012";
+static PASSWORD: &[u8] = b"correct horse battery staple";
+
+#[test]
+fn wallet_test() {
+    let mut wallet = Wallet::new();
+    let address = wallet.generate_key();
+    let imported = wallet.import_key(&[7u8; 32]).unwrap();
+    assert_eq!(imported, address_from_secret_key(&[7u8; 32]).unwrap());
+    assert!(wallet.import_key(&[0u8; 32]).is_err());
+
+    let mut tr = Transaction::new_versioned(
+        &address,
+        RECIEVER,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        0,
+    );
+    wallet.sign(&mut tr, PREV_HASH).unwrap();
+    assert!(tr.verify(PREV_HASH).unwrap());
+
+    // keystore round trip
+    let dump = wallet.dump(PASSWORD).unwrap();
+    let restored = Wallet::parse(&dump, PASSWORD).unwrap();
+    assert_eq!(restored.len(), 2);
+    assert!(restored.contains(&address));
+    assert_eq!(restored.get_secret_key(&imported), Some(&[7u8; 32]));
+
+    assert!(Wallet::parse(&dump, b"wrong password").is_err());
+
+    // stored round count can't be changed
+    let mut weakened = dump.clone();
+    weakened[1..5].copy_from_slice(&1u32.to_be_bytes());
+    assert!(Wallet::parse(&weakened, PASSWORD).is_err());
+    let mut expensive = dump.clone();
+    expensive[1..5].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert!(Wallet::parse(&expensive, PASSWORD).is_err());
+
+    // unknown sender can't be signed
+    let mut foreign = Transaction::new_versioned(
+        RECIEVER,
+        &address,
+        121212,
+        &[0u8; 64],
+        100u64.to_biguint().unwrap(),
+        1u64.to_biguint().unwrap(),
+        0,
+    );
+    assert!(wallet.sign(&mut foreign, PREV_HASH).is_err());
+}